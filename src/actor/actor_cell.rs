@@ -1,19 +1,26 @@
 use std::{
+    any::TypeId,
     collections::HashMap,
     fmt,
     ops::Deref,
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc, RwLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
     },
     time::{Duration, Instant},
 };
 
 use chrono::prelude::*;
-use futures::{future::RemoteHandle, task::SpawnError, Future};
+use futures::{
+    future::{abortable, AbortHandle, RemoteHandle},
+    task::{SpawnError, SpawnExt},
+    Future, FutureExt,
+};
 use uuid::Uuid;
 
 use rand;
+use serde::Serialize;
+use slog::warn;
 
 use crate::actor_ref::{
     ActorRef, ActorRefFactory, ActorReference, BasicActorRef, Tell, TmpActorRefFactory,
@@ -26,7 +33,8 @@ use crate::{
     },
     system::{
         timer::{Job, OnceJob, RepeatJob, Timer},
-        ActorSystem, Run, SystemCmd, SystemMsg,
+        ActorSystem, EventQuery, EventStream, Evt, PersistError, PersistenceConf, Run, Snapshot,
+        SystemCmd, SystemMsg,
     },
     validate::InvalidPath,
     AnyMessage, Envelope, Message,
@@ -43,14 +51,16 @@ struct ActorCellInner {
     uri: ActorUri,
     parent: Option<BasicActorRef>,
     children: Children,
-    is_remote: bool,
+    remote: Option<ActorAddress>,
     is_terminating: Arc<AtomicBool>,
     is_restarting: Arc<AtomicBool>,
-    // persistence: Persistence,
+    persistence: Persistence,
+    tasks: Tasks,
     status: Arc<AtomicUsize>,
     kernel: Option<KernelRef>,
     system: ActorSystem,
     mailbox: Arc<dyn AnySender>,
+    msg_type: TypeId,
     sys_mailbox: MailboxSender<SystemMsg>,
 }
 
@@ -63,6 +73,7 @@ impl ActorCell {
         system: &ActorSystem,
         // perconf: Option<PersistenceConf>,
         mailbox: Arc<dyn AnySender>,
+        msg_type: TypeId,
         sys_mailbox: MailboxSender<SystemMsg>,
     ) -> Self {
         Self {
@@ -71,18 +82,16 @@ impl ActorCell {
                 uri,
                 parent,
                 children: Children::new(),
-                is_remote: false,
+                remote: None,
                 is_terminating: Arc::new(AtomicBool::new(false)),
                 is_restarting: Arc::new(AtomicBool::new(false)),
-                // persistence: Persistence {
-                //     // event_store: system.event_store.clone(),
-                //     is_persisting: Arc::new(AtomicBool::new(false)),
-                //     persistence_conf: perconf,
-                // },
+                persistence: Persistence::default(),
+                tasks: Tasks::default(),
                 status: Arc::new(AtomicUsize::new(0)),
                 kernel: None,
                 system: system.clone(),
                 mailbox,
+                msg_type,
                 sys_mailbox,
             }),
         }
@@ -99,6 +108,25 @@ impl ActorCell {
         }
     }
 
+    /// Returns a copy of this cell that represents the actor at
+    /// `address`, e.g. the local proxy of a remote actor
+    pub(crate) fn remote(self, address: ActorAddress) -> Self {
+        let uri = ActorUri {
+            host: Arc::new(address.authority()),
+            ..self.inner.uri.clone()
+        };
+
+        let inner = ActorCellInner {
+            uri,
+            remote: Some(address),
+            ..self.inner.deref().clone()
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
     pub(crate) fn kernel(&self) -> &KernelRef {
         self.inner.kernel.as_ref().unwrap()
     }
@@ -135,6 +163,22 @@ impl ActorCell {
         self.inner.system.user_root().is_child(&self.myself())
     }
 
+    /// True if the actor belongs to another actor system, in which
+    /// case messages are serialized and sent over the network
+    pub fn is_remote(&self) -> bool {
+        self.inner.remote.is_some()
+    }
+
+    pub(crate) fn address(&self) -> ActorAddress {
+        match self.inner.remote {
+            Some(ref address) => address.clone(),
+            None => {
+                let sys = &self.inner.system;
+                ActorAddress::new(&sys.name(), &sys.host(), self.inner.uri.path.clone())
+            }
+        }
+    }
+
     pub(crate) fn send_any_msg(
         &self,
         msg: &mut AnyMessage,
@@ -146,6 +190,28 @@ impl ActorCell {
         dispatch_any(msg, sender, mb, k, &self.inner.system)
     }
 
+    /// True if the actor's message type is `Msg`
+    pub(crate) fn accepts<Msg: Message>(&self) -> bool {
+        self.inner.msg_type == TypeId::of::<Msg>()
+    }
+
+    /// The cell extended with the actor's mailbox, if the actor's
+    /// message type is `Msg`
+    pub(crate) fn typed<Msg: Message>(&self) -> Option<ExtendedCell<Msg>> {
+        if !self.accepts::<Msg>() {
+            return None;
+        }
+
+        self.inner
+            .mailbox
+            .as_any()
+            .downcast_ref::<MailboxSender<Msg>>()
+            .map(|mailbox| ExtendedCell {
+                cell: self.clone(),
+                mailbox: Mailbox::Queue(mailbox.clone()),
+            })
+    }
+
     pub(crate) fn send_sys_msg(&self, msg: Envelope<SystemMsg>) -> MsgResult<Envelope<SystemMsg>> {
         let mb = &self.inner.sys_mailbox;
 
@@ -162,17 +228,37 @@ impl ActorCell {
         actor.sys_tell(SystemCmd::Stop.into());
     }
 
-    // pub(crate) fn persistence_conf(&self) -> Option<PersistenceConf> {
-    //     self.inner.persistence.persistence_conf.clone()
-    // }
+    pub(crate) fn persistence_conf(&self) -> Option<PersistenceConf> {
+        self.inner.persistence.conf.read().unwrap().clone()
+    }
 
-    // pub fn is_persisting(&self) -> bool {
-    //     self.inner.persistence.is_persisting.load(Ordering::Relaxed)
-    // }
+    /// Run a future on the system's executor until it completes or the
+    /// actor stops, whichever is first. If `until_restart` is set the
+    /// future is also aborted if the actor restarts.
+    pub(crate) fn run_task<Fut>(&self, future: Fut, until_restart: bool) -> Result<(), SpawnError>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (future, handle) = abortable(future);
+        let tasks = self.inner.tasks.clone();
+        let id = tasks.add(handle, until_restart);
+
+        self.inner
+            .system
+            .exec
+            .spawn(future.map(move |_| tasks.remove(id)))
+    }
+
+    /// Abort the futures still running for the actor. Futures run after
+    /// the actor stops are aborted straight away.
+    pub(crate) fn abort_tasks(&self) {
+        self.inner.tasks.abort_all();
+    }
 
-    // pub fn set_persisting(&self, b: bool) {
-    //     self.inner.persistence.is_persisting.store(b, Ordering::Relaxed);
-    // }
+    /// Abort the futures running for the actor that don't outlive a restart
+    pub(crate) fn abort_restarted_tasks(&self) {
+        self.inner.tasks.abort_until_restart();
+    }
 
     pub fn add_child(&self, actor: BasicActorRef) {
         self.inner.children.add(actor);
@@ -257,44 +343,6 @@ impl ActorCell {
             .unwrap()
             .sys_tell(SystemMsg::Failed(self.myself()));
     }
-
-    // pub fn load_events<A: Actor>(&self, actor: &mut Option<A>) -> bool {
-    //     let event_store = &self.inner.persistence.event_store;
-    //     let perconf = &self.inner.persistence.persistence_conf;
-
-    //     match (actor, event_store, perconf) {
-    //         (Some(_), Some(es), Some(perconf)) => {
-    //             let myself = self.myself();
-    //             // query(&perconf.id,
-    //             //         &perconf.keyspace,
-    //             //         &es,
-    //             //         self,
-    //             //         myself); // todo implement
-
-    //             false
-    //         }
-    //         (Some(_), None, Some(_)) => {
-    //             warn!("Can't load actor events. No event store configured");
-    //             true
-    //         }
-    //         _ => {
-    //             // anything else either the actor is None or there's no persistence configured
-    //             true
-    //         }
-    //     }
-    //     unimplemented!()
-    // }
-
-    // pub fn replay<A: Actor>(&self,
-    //             ctx: &Context<A::Msg>,
-    //             evts: Vec<A::Msg>,
-    //             actor: &mut Option<A>) {
-    //     if let Some(actor) = actor.as_mut() {
-    //         for event in evts.iter() {
-    //             actor.replay_event(ctx, event.clone());
-    //         }
-    //     }
-    // }
 }
 
 impl<Msg: Message> From<ExtendedCell<Msg>> for ActorCell {
@@ -351,7 +399,17 @@ impl TmpActorRefFactory for ActorCell {
 #[derive(Clone)]
 pub struct ExtendedCell<Msg: Message> {
     cell: ActorCell,
-    mailbox: MailboxSender<Msg>,
+    mailbox: Mailbox<Msg>,
+}
+
+/// Where the messages sent through an `ExtendedCell` go
+#[derive(Clone)]
+enum Mailbox<Msg: Message> {
+    /// The actor's own mailbox
+    Queue(MailboxSender<Msg>),
+    /// A message adapter, converting messages and sending them to the
+    /// mailbox of the actor that owns the cell
+    Adapter(Arc<dyn Fn(Envelope<Msg>) + Send + Sync>),
 }
 
 impl<Msg> ExtendedCell<Msg>
@@ -374,23 +432,24 @@ where
                 uri,
                 parent,
                 children: Children::new(),
-                is_remote: false,
+                remote: None,
                 is_terminating: Arc::new(AtomicBool::new(false)),
                 is_restarting: Arc::new(AtomicBool::new(false)),
-                // persistence: Persistence {
-                //     // event_store: system.event_store.clone(),
-                //     is_persisting: Arc::new(AtomicBool::new(false)),
-                //     persistence_conf: perconf,
-                // },
+                persistence: Persistence::default(),
+                tasks: Tasks::default(),
                 status: Arc::new(AtomicUsize::new(0)),
                 kernel: None,
                 system: system.clone(),
                 mailbox: any_mailbox,
+                msg_type: TypeId::of::<Msg>(),
                 sys_mailbox,
             }),
         };
 
-        Self { cell, mailbox }
+        Self {
+            cell,
+            mailbox: Mailbox::Queue(mailbox),
+        }
     }
 
     pub(crate) fn init(self, kernel: &KernelRef) -> Self {
@@ -399,6 +458,12 @@ where
         Self { cell, ..self }
     }
 
+    pub(crate) fn remote(self, address: ActorAddress) -> Self {
+        let cell = self.cell.remote(address);
+
+        Self { cell, ..self }
+    }
+
     pub fn myself(&self) -> ActorRef<Msg> {
         self.cell.myself().typed(self.clone())
     }
@@ -435,8 +500,22 @@ where
         self.cell.is_user()
     }
 
+    pub fn is_remote(&self) -> bool {
+        self.cell.is_remote()
+    }
+
+    pub(crate) fn address(&self) -> ActorAddress {
+        self.cell.address()
+    }
+
     pub(crate) fn send_msg(&self, msg: Envelope<Msg>) -> MsgResult<Envelope<Msg>> {
-        let mb = &self.mailbox;
+        let mb = match self.mailbox {
+            Mailbox::Queue(ref mb) => mb,
+            Mailbox::Adapter(ref forward) => {
+                forward(msg);
+                return Ok(());
+            }
+        };
         let k = self.cell.kernel();
 
         dispatch(msg, mb, k, self.system()).map_err(|e| {
@@ -478,6 +557,114 @@ where
     pub(crate) fn death_watch<A: Actor>(&self, terminated: &BasicActorRef, actor: &mut Option<A>) {
         self.cell.death_watch(terminated, actor)
     }
+
+    /// Recovers the state of a persistent actor.
+    ///
+    /// The latest snapshot is loaded first and then only the events
+    /// persisted after the snapshot are replayed.
+    pub(crate) fn recover<A>(&self, ctx: &Context<Msg>, actor: &mut A)
+    where
+        A: Actor<Msg = Msg>,
+    {
+        let Some(conf) = actor.persistence_conf() else {
+            return;
+        };
+
+        let sys = self.system();
+        let mut seq_nr = 0;
+
+        if let Some(store) = sys.snapshot_store() {
+            match store.load_latest(&conf.id, &conf.keyspace) {
+                Ok(Some(snapshot)) => {
+                    seq_nr = snapshot.meta.seq_nr;
+                    actor.recover_snapshot(ctx, snapshot);
+                }
+                Ok(None) => {}
+                Err(e) => warn!(sys.log(), "Failed to load snapshot: {}", e),
+            }
+        }
+
+        if let Some(store) = sys.event_store::<Msg>() {
+            for evt in store.load_from(&conf.id, &conf.keyspace, seq_nr + 1) {
+                seq_nr = evt.seq_nr;
+                actor.replay_event(ctx, evt.msg);
+            }
+        } else {
+            warn!(
+                sys.log(),
+                "Can't load actor events. No event store configured"
+            );
+        }
+
+        let persistence = &self.cell.inner.persistence;
+        persistence.seq_nr.store(seq_nr, Ordering::Relaxed);
+        *persistence.conf.write().unwrap() = Some(conf);
+    }
+
+    pub(crate) fn persist_event(&self, evt: Msg, tags: Vec<String>) -> Result<u64, PersistError> {
+        let conf = self
+            .cell
+            .persistence_conf()
+            .ok_or(PersistError::NotPersistent)?;
+        let store = self
+            .system()
+            .event_store::<Msg>()
+            .ok_or(PersistError::NoEventStore)?;
+
+        let seq_nr = self.cell.inner.persistence.seq_nr.load(Ordering::Relaxed) + 1;
+        let evt = Evt {
+            tags,
+            ..Evt::new(seq_nr, evt)
+        };
+
+        store.insert(&conf.id, &conf.keyspace, evt)?;
+        self.cell
+            .inner
+            .persistence
+            .seq_nr
+            .store(seq_nr, Ordering::Relaxed);
+
+        Ok(seq_nr)
+    }
+
+    pub(crate) fn save_snapshot<S: Serialize>(&self, state: &S) -> Result<u64, PersistError> {
+        let conf = self
+            .cell
+            .persistence_conf()
+            .ok_or(PersistError::NotPersistent)?;
+        let store = self
+            .system()
+            .snapshot_store()
+            .ok_or(PersistError::NoSnapshotStore)?;
+
+        let seq_nr = self.cell.inner.persistence.seq_nr.load(Ordering::Relaxed);
+        store.save(&conf.id, &conf.keyspace, &Snapshot::new(seq_nr, state)?)?;
+
+        // apply the retention policy now that the new snapshot is safely stored
+        let retention = &conf.retention;
+        if retention.keep_snapshots.is_none() && !retention.delete_events {
+            return Ok(seq_nr);
+        }
+
+        let snapshots = store.list(&conf.id, &conf.keyspace)?;
+        let remove = retention
+            .keep_snapshots
+            .map_or(0, |keep| snapshots.len().saturating_sub(keep.max(1)));
+
+        for meta in &snapshots[..remove] {
+            store.delete(&conf.id, &conf.keyspace, meta)?;
+        }
+
+        if retention.delete_events {
+            if let (Some(oldest), Some(events)) =
+                (snapshots.get(remove), self.system().event_store::<Msg>())
+            {
+                events.delete_to(&conf.id, &conf.keyspace, oldest.seq_nr);
+            }
+        }
+
+        Ok(seq_nr)
+    }
 }
 
 impl<Msg: Message> fmt::Debug for ExtendedCell<Msg> {
@@ -504,8 +691,8 @@ fn post_stop<A: Actor>(actor: &mut Option<A>) {
 /// actor's perspective. For example, creating a child actor
 /// using `ctx.actor_of` will create the child under the current
 /// actor within the heirarchy. In a similar manner, persistence
-/// operations such as `persist_event` use the current actor's
-/// persistence configuration.
+/// operations such as `persist_event` and `save_snapshot` use the
+/// current actor's persistence configuration.
 ///
 /// Since `Context` is specific to an actor and its functions
 /// it is not cloneable.
@@ -532,6 +719,134 @@ where
     pub fn name(&self) -> &str {
         self.myself.name()
     }
+
+    /// Persist an event to the event store registered for `Msg`.
+    ///
+    /// The event is written before `persist_event` returns, so it is
+    /// safe to update the actor's state once the sequence number of the
+    /// event is returned.
+    pub fn persist_event(&self, evt: Msg) -> Result<u64, PersistError> {
+        self.myself.cell.persist_event(evt, Vec::new())
+    }
+
+    /// Persist an event tagged with one or more tags.
+    ///
+    /// Tagged events can be queried across persistent actors using
+    /// `EventQuery::events_by_tag`, e.g. to build read models.
+    pub fn persist_tagged(&self, evt: Msg, tags: &[&str]) -> Result<u64, PersistError> {
+        let tags = tags.iter().map(|tag| (*tag).to_string()).collect();
+        self.myself.cell.persist_event(evt, tags)
+    }
+
+    /// Save a snapshot of the actor's state to the registered snapshot store.
+    ///
+    /// The snapshot covers all events persisted so far. When the actor next
+    /// recovers only events persisted after the snapshot are replayed.
+    /// Old snapshots and events are removed according to the
+    /// `Retention` of the actor's `PersistenceConf`.
+    pub fn save_snapshot<S: Serialize>(&self, state: &S) -> Result<u64, PersistError> {
+        self.myself.cell.save_snapshot(state)
+    }
+
+    /// Create a reference that accepts messages of another type on behalf
+    /// of the current actor, e.g. replies in a service's own protocol.
+    ///
+    /// Messages sent to the adapter are converted with `convert` and
+    /// forwarded to the current actor, keeping their sender:
+    ///
+    /// ```ignore
+    /// let reply_to = ctx.message_adapter(|res: ServiceResponse| MyMsg::Service(res));
+    /// service.tell(ServiceRequest { reply_to }, None);
+    /// ```
+    ///
+    /// The adapter isn't an actor. It shares the identity of the current
+    /// actor, and messages sent to it are converted and added to the
+    /// current actor's mailbox when they're sent. Once the current actor
+    /// stops they go to dead letters.
+    pub fn message_adapter<T, F>(&self, convert: F) -> ActorRef<T>
+    where
+        T: Message,
+        F: Fn(T) -> Msg + Send + Sync + 'static,
+    {
+        let owner = self.myself.cell.clone();
+        let forward = move |msg: Envelope<T>| {
+            let msg = Envelope {
+                msg: convert(msg.msg),
+                sender: msg.sender,
+            };
+            let _ = owner.send_msg(msg);
+        };
+        let cell = ExtendedCell {
+            cell: self.myself.cell.cell.clone(),
+            mailbox: Mailbox::Adapter(Arc::new(forward)),
+        };
+        self.myself.cell.cell.myself().typed(cell)
+    }
+
+    /// Run a future and send its output to the current actor as a
+    /// message, converted with `f`:
+    ///
+    /// ```ignore
+    /// ctx.pipe_to(fetch(url), |page| MyMsg::Fetched(page))?;
+    /// ```
+    ///
+    /// If the actor stops before the future completes the future is
+    /// dropped and no message is sent.
+    pub fn pipe_to<Fut, F>(&self, future: Fut, f: F) -> Result<(), SpawnError>
+    where
+        Fut: Future + Send + 'static,
+        F: FnOnce(Fut::Output) -> Msg + Send + 'static,
+    {
+        self.pipe_to_actor(future, self.myself(), f)
+    }
+
+    /// Run a future and send its output to `actor` as a message,
+    /// converted with `f`.
+    ///
+    /// The message is sent with the current actor as its sender. As with
+    /// `pipe_to`, the future is dropped if the current actor stops before
+    /// it completes.
+    pub fn pipe_to_actor<T, Fut, F>(
+        &self,
+        future: Fut,
+        actor: impl Tell<T>,
+        f: F,
+    ) -> Result<(), SpawnError>
+    where
+        Fut: Future + Send + 'static,
+        F: FnOnce(Fut::Output) -> T + Send + 'static,
+    {
+        let sender = self.myself().into();
+        self.myself.cell.cell.run_task(
+            future.map(move |output| actor.tell(f(output), sender)),
+            false,
+        )
+    }
+
+    /// Run a future on the system's executor until it completes or the
+    /// current actor stops, whichever is first.
+    ///
+    /// Unlike a future run with `run`, the future doesn't outlive the
+    /// actor. It does keep running if the actor restarts, use
+    /// `spawn_until_restart` for futures that shouldn't.
+    pub fn spawn<Fut>(&self, future: Fut) -> Result<(), SpawnError>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.myself.cell.cell.run_task(future, false)
+    }
+
+    /// Run a future on the system's executor until it completes, or the
+    /// current actor stops or restarts, whichever is first.
+    ///
+    /// Useful for futures that only make sense for the current instance
+    /// of the actor, since its state is reset when it restarts.
+    pub fn spawn_until_restart<Fut>(&self, future: Fut) -> Result<(), SpawnError>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.myself.cell.cell.run_task(future, true)
+    }
 }
 
 impl<Msg: Message> ActorRefFactory for Context<Msg> {
@@ -587,6 +902,10 @@ where
     Msg: Message,
 {
     fn select(&self, path: &str) -> Result<ActorSelection, InvalidPath> {
+        if path.starts_with("riker://") {
+            return self.system.select(path);
+        }
+
         let (anchor, path_str) = if path.starts_with('/') {
             let anchor = self.system.user_root().clone();
             let anchor_path = format!("{}/", anchor.path().deref().clone());
@@ -617,6 +936,24 @@ where
     }
 }
 
+impl<Msg> EventQuery for Context<Msg>
+where
+    Msg: Message,
+{
+    fn events_by_tag<M: Message>(&self, tag: &str, from_offset: u64) -> EventStream<M> {
+        self.system.events_by_tag(tag, from_offset)
+    }
+
+    fn events_by_id<M: Message>(
+        &self,
+        id: &str,
+        keyspace: &str,
+        from_seq_nr: u64,
+    ) -> EventStream<M> {
+        self.system.events_by_id(id, keyspace, from_seq_nr)
+    }
+}
+
 impl<Msg> Timer for Context<Msg>
 where
     Msg: Message,
@@ -710,6 +1047,72 @@ where
     }
 }
 
+/// The futures running for an actor, by id
+#[derive(Clone, Default)]
+struct Tasks {
+    inner: Arc<Mutex<TasksInner>>,
+}
+
+#[derive(Default)]
+struct TasksInner {
+    next_id: u64,
+    handles: HashMap<u64, Task>,
+    stopped: bool,
+}
+
+struct Task {
+    handle: AbortHandle,
+    until_restart: bool,
+}
+
+impl Tasks {
+    fn add(&self, handle: AbortHandle, until_restart: bool) -> u64 {
+        let mut tasks = self.inner.lock().unwrap();
+        if tasks.stopped {
+            handle.abort();
+        }
+
+        let id = tasks.next_id;
+        tasks.next_id += 1;
+        tasks.handles.insert(
+            id,
+            Task {
+                handle,
+                until_restart,
+            },
+        );
+        id
+    }
+
+    fn remove(&self, id: u64) {
+        self.inner.lock().unwrap().handles.remove(&id);
+    }
+
+    fn abort_all(&self) {
+        let mut tasks = self.inner.lock().unwrap();
+        tasks.stopped = true;
+        for (_, task) in tasks.handles.drain() {
+            task.handle.abort();
+        }
+    }
+
+    fn abort_until_restart(&self) {
+        let mut tasks = self.inner.lock().unwrap();
+        tasks.handles.retain(|_, task| {
+            if task.until_restart {
+                task.handle.abort();
+            }
+            !task.until_restart
+        });
+    }
+}
+
+#[derive(Clone, Default)]
+struct Persistence {
+    conf: Arc<RwLock<Option<PersistenceConf>>>,
+    seq_nr: Arc<AtomicU64>,
+}
+
 #[derive(Clone)]
 pub struct Children {
     actors: Arc<RwLock<HashMap<String, BasicActorRef>>>,
@@ -1,12 +1,12 @@
-use std::fmt;
+use std::{fmt, sync::Arc};
 
 use crate::{
     actor::{
         actor_cell::{ActorCell, ExtendedCell},
         props::{ActorArgs, ActorFactory, ActorFactoryArgs},
-        Actor, ActorPath, ActorUri, BoxActorProd, CreateError,
+        Actor, ActorAddress, ActorPath, ActorUri, BoxActorProd, CreateError,
     },
-    system::{ActorSystem, SystemMsg},
+    system::{ActorSystem, SystemEvent, SystemMsg},
     AnyMessage, Envelope, Message,
 };
 
@@ -26,6 +26,12 @@ pub trait ActorReference {
     /// e.g. `/user/actor_a/actor_b`
     fn path(&self) -> &ActorPath;
 
+    /// Actor address.
+    ///
+    /// The full address of the actor including its actor system,
+    /// e.g. `riker://my-app@localhost/user/actor_a`
+    fn address(&self) -> ActorAddress;
+
     /// True if this actor is the top level root
     ///
     /// I.e. `/root`
@@ -59,6 +65,40 @@ pub type BoxedTell<T> = Box<dyn Tell<T> + Send + 'static>;
 pub trait Tell<T>: ActorReference + Send + 'static {
     fn tell(&self, msg: T, sender: Option<BasicActorRef>);
     fn box_clone(&self) -> BoxedTell<T>;
+
+    /// A reference that accepts messages of type `U`, converting them
+    /// with `convert` before they're sent to this actor.
+    ///
+    /// No actor is created, the message is converted when it's sent.
+    /// The reference can be used wherever a `BoxedTell<U>` is expected,
+    /// such as to subscribe the actor to a channel of another type:
+    ///
+    /// ```ignore
+    /// let actor = Box::new(my_actor.contramap(|evt: OrderEvent| MyMsg::Order(evt)));
+    /// orders.tell(Subscribe { actor, topic: "orders".into() }, None);
+    /// ```
+    fn contramap<U, F>(&self, convert: F) -> Contramap<U, T>
+    where
+        Self: Sized,
+        U: Message,
+        T: Message,
+        F: Fn(U) -> T + Send + Sync + 'static,
+    {
+        Contramap {
+            actor: self.box_clone(),
+            convert: Arc::new(convert),
+        }
+    }
+
+    /// Identifies the conversion of a reference created by `contramap`.
+    ///
+    /// Subscriptions are told apart by the actor's path and this id, so an
+    /// actor can be subscribed to a channel both directly and through one
+    /// or more conversions.
+    #[doc(hidden)]
+    fn conversion(&self) -> Option<usize> {
+        None
+    }
 }
 
 impl<T, M> Tell<T> for ActorRef<M>
@@ -97,6 +137,10 @@ where
         (**self).path()
     }
 
+    fn address(&self) -> ActorAddress {
+        (**self).address()
+    }
+
     fn is_root(&self) -> bool {
         (**self).is_root()
     }
@@ -128,9 +172,26 @@ where
     }
 }
 
-impl<T> PartialEq for BoxedTell<T> {
+impl<T> Tell<T> for BoxedTell<T>
+where
+    T: Message,
+{
+    fn tell(&self, msg: T, sender: Sender) {
+        (**self).tell(msg, sender);
+    }
+
+    fn box_clone(&self) -> Self {
+        (**self).box_clone()
+    }
+
+    fn conversion(&self) -> Option<usize> {
+        (**self).conversion()
+    }
+}
+
+impl<T: 'static> PartialEq for BoxedTell<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.path() == other.path()
+        self.path() == other.path() && self.conversion() == other.conversion()
     }
 }
 
@@ -152,6 +213,98 @@ impl<T: 'static> Clone for BoxedTell<T> {
     }
 }
 
+/// A reference that converts messages of type `T` to the message type
+/// `M` of the underlying actor before telling them.
+///
+/// Created by `Tell::contramap`.
+pub struct Contramap<T, M> {
+    actor: BoxedTell<M>,
+    convert: Arc<dyn Fn(T) -> M + Send + Sync>,
+}
+
+impl<T, M> Tell<T> for Contramap<T, M>
+where
+    T: Message,
+    M: Message,
+{
+    fn tell(&self, msg: T, sender: Sender) {
+        self.actor.tell((self.convert)(msg), sender);
+    }
+
+    fn box_clone(&self) -> BoxedTell<T> {
+        Box::new(self.clone())
+    }
+
+    fn conversion(&self) -> Option<usize> {
+        Some(Arc::as_ptr(&self.convert).cast::<()>() as usize)
+    }
+}
+
+impl<T, M> ActorReference for Contramap<T, M>
+where
+    T: Message,
+    M: Message,
+{
+    fn name(&self) -> &str {
+        self.actor.name()
+    }
+
+    fn uri(&self) -> &ActorUri {
+        self.actor.uri()
+    }
+
+    fn path(&self) -> &ActorPath {
+        self.actor.path()
+    }
+
+    fn address(&self) -> ActorAddress {
+        self.actor.address()
+    }
+
+    fn is_root(&self) -> bool {
+        self.actor.is_root()
+    }
+
+    fn parent(&self) -> BasicActorRef {
+        self.actor.parent()
+    }
+
+    fn user_root(&self) -> BasicActorRef {
+        self.actor.user_root()
+    }
+
+    fn has_children(&self) -> bool {
+        self.actor.has_children()
+    }
+
+    fn is_child(&self, actor: &BasicActorRef) -> bool {
+        self.actor.is_child(actor)
+    }
+
+    fn children<'a>(&'a self) -> Box<dyn Iterator<Item = BasicActorRef> + 'a> {
+        self.actor.children()
+    }
+
+    fn sys_tell(&self, msg: SystemMsg) {
+        self.actor.sys_tell(msg);
+    }
+}
+
+impl<T, M: Message> Clone for Contramap<T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            actor: self.actor.box_clone(),
+            convert: self.convert.clone(),
+        }
+    }
+}
+
+impl<T, M: Message> fmt::Debug for Contramap<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Contramap[{:?}]", self.actor.uri())
+    }
+}
+
 /// A lightweight, un-typed reference to interact with its underlying
 /// actor instance through concurrent messaging.
 ///
@@ -183,6 +336,28 @@ impl BasicActorRef {
         ActorRef { cell }
     }
 
+    /// True if the actor's message type is `Msg`, in which case
+    /// messages of type `Msg` sent with `try_tell` are accepted
+    #[must_use]
+    pub fn accepts<Msg: Message>(&self) -> bool {
+        self.cell.accepts::<Msg>()
+    }
+
+    /// A typed reference to the actor, if its message type is `Msg`
+    ///
+    /// This recovers an `ActorRef` from references obtained from
+    /// `children()`, a selection or a message's sender:
+    ///
+    /// ```ignore
+    /// if let Some(actor) = sender.typed_as::<MyMsg>() {
+    ///     actor.tell(MyMsg, None);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn typed_as<Msg: Message>(&self) -> Option<ActorRef<Msg>> {
+        self.cell.typed().map(ActorRef::new)
+    }
+
     pub(crate) fn sys_init(&self, sys: &ActorSystem) {
         self.cell.kernel().sys_init(sys);
     }
@@ -225,6 +400,10 @@ impl ActorReference for BasicActorRef {
         &self.cell.uri().path
     }
 
+    fn address(&self) -> ActorAddress {
+        self.cell.address()
+    }
+
     fn is_root(&self) -> bool {
         self.cell.is_root()
     }
@@ -276,6 +455,10 @@ impl ActorReference for &BasicActorRef {
         &self.cell.uri().path
     }
 
+    fn address(&self) -> ActorAddress {
+        self.cell.address()
+    }
+
     fn is_root(&self) -> bool {
         self.cell.is_root()
     }
@@ -320,6 +503,19 @@ impl fmt::Display for BasicActorRef {
     }
 }
 
+/// System events told to a `BasicActorRef` are sent as system messages
+/// and received by `Actor::sys_recv`, so any actor can subscribe to a
+/// channel of `SystemEvent`, whatever its message type.
+impl Tell<SystemEvent> for BasicActorRef {
+    fn tell(&self, msg: SystemEvent, _sender: Sender) {
+        self.sys_tell(SystemMsg::Event(msg));
+    }
+
+    fn box_clone(&self) -> BoxedTell<SystemEvent> {
+        Box::new(self.clone())
+    }
+}
+
 impl PartialEq for BasicActorRef {
     fn eq(&self, other: &Self) -> bool {
         self.cell.uri().path == other.cell.uri().path
@@ -404,6 +600,10 @@ impl<Msg: Message> ActorReference for ActorRef<Msg> {
         &self.cell.uri().path
     }
 
+    fn address(&self) -> ActorAddress {
+        self.cell.address()
+    }
+
     fn is_root(&self) -> bool {
         self.cell.is_root()
     }
@@ -455,6 +655,10 @@ impl<Msg: Message> ActorReference for &ActorRef<Msg> {
         &self.cell.uri().path
     }
 
+    fn address(&self) -> ActorAddress {
+        self.cell.address()
+    }
+
     fn is_root(&self) -> bool {
         self.cell.is_root()
     }
@@ -0,0 +1,127 @@
+use futures::future::BoxFuture;
+
+use crate::actor_ref::Sender;
+use crate::{
+    actor::{Actor, ActorArgs, ActorFactoryArgs, Context, Strategy},
+    system::SystemMsg,
+    Message,
+};
+
+/// An actor that handles each message with a future.
+///
+/// `recv` returns a boxed future, typically an `async move` block, that
+/// is awaited by the actor's kernel before the next message is taken
+/// from the mailbox. Only one message is handled at any time, as with
+/// `Actor`, but a handler waiting on I/O doesn't block a thread of the
+/// system's executor.
+///
+/// ```ignore
+/// impl AsyncActor for Fetcher {
+///     type Msg = Url;
+///
+///     fn recv<'a>(&'a mut self, ctx: &'a Context<Url>, msg: Url, _sender: Sender) -> BoxFuture<'a, ()> {
+///         Box::pin(async move {
+///             self.pages += fetch(msg).await;
+///         })
+///     }
+/// }
+/// ```
+///
+/// A panic in the future is handled by the supervisor of the actor like
+/// a panic in `Actor::recv`.
+///
+/// An `AsyncActor` is started wrapped in `Async`, e.g.
+/// `sys.actor_of::<Async<Fetcher>>("fetcher")`.
+pub trait AsyncActor: Send + 'static {
+    type Msg: Message;
+
+    /// Invoked when an actor is being started by the system.
+    ///
+    /// See `Actor::pre_start`.
+    fn pre_start(&mut self, _ctx: &Context<Self::Msg>) {}
+
+    /// Invoked after an actor has started.
+    fn post_start(&mut self, _ctx: &Context<Self::Msg>) {}
+
+    /// Invoked after an actor has been stopped.
+    fn post_stop(&mut self) {}
+
+    /// Return a supervisor strategy that will be used when handling failed child actors.
+    fn supervisor_strategy(&self) -> Strategy {
+        Strategy::Restart
+    }
+
+    /// Invoked when an actor receives a message
+    ///
+    /// The next message isn't handled until the returned future completes.
+    fn recv<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> BoxFuture<'a, ()>;
+
+    /// Invoked when an actor receives a system message
+    fn sys_recv(&mut self, _ctx: &Context<Self::Msg>, _msg: SystemMsg, _sender: Sender) {}
+}
+
+/// Runs an `AsyncActor` as an actor of the system
+///
+/// An actor created with arguments is converted from them with `From`,
+/// e.g. `sys.actor_of_args::<Async<Fetcher>, _>("fetcher", config)`
+/// where `Fetcher` implements `From<Config>`.
+#[derive(Default)]
+pub struct Async<A>(pub A);
+
+impl<A, Args> ActorFactoryArgs<Args> for Async<A>
+where
+    A: AsyncActor + From<Args>,
+    Args: ActorArgs,
+{
+    fn create_args(args: Args) -> Self {
+        Self(A::from(args))
+    }
+}
+
+impl<A: AsyncActor> Actor for Async<A> {
+    type Msg = A::Msg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.0.pre_start(ctx);
+    }
+
+    fn post_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.0.post_start(ctx);
+    }
+
+    fn post_stop(&mut self) {
+        self.0.post_stop();
+    }
+
+    fn supervisor_strategy(&self) -> Strategy {
+        self.0.supervisor_strategy()
+    }
+
+    /// Sends the message to the actor's mailbox, from which the kernel
+    /// handles it with `recv_future`, so that the calling thread isn't
+    /// blocked while the handler runs.
+    ///
+    /// The kernel doesn't call `recv`. An actor that wraps an async actor
+    /// must forward `recv_future` to it, as `Box` does.
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        ctx.myself().send_msg(msg, sender);
+    }
+
+    fn recv_future<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> Option<BoxFuture<'a, ()>> {
+        Some(self.0.recv(ctx, msg, sender))
+    }
+
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, sender: Sender) {
+        self.0.sys_recv(ctx, msg, sender);
+    }
+}
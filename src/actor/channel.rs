@@ -1,18 +1,172 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    hash::Hash,
+    ptr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::actor_ref::{
-    ActorRef, ActorRefFactory, ActorReference, BasicActorRef, BoxedTell, Sender,
+    ActorRef, ActorRefFactory, ActorReference, BasicActorRef, BoxedTell, Sender, Tell,
 };
 use crate::{
-    actor::{Actor, Context, CreateError, Receive},
+    actor::{Actor, ActorFactoryArgs, Context, CreateError, Receive},
     system::{SystemEvent, SystemMsg},
     Message,
 };
 
-type Subs<Msg> = HashMap<Topic, Vec<BoxedTell<Msg>>>;
+/// An actor subscribed to a topic and the filter of the messages it receives
+#[derive(Clone)]
+pub struct Subscriber<Msg: Message> {
+    pub actor: BoxedTell<Msg>,
+    pub filter: Option<Filter<Msg>>,
+}
+
+impl<Msg: Message> Subscriber<Msg> {
+    pub fn accepts(&self, msg: &Msg) -> bool {
+        self.filter.iter().all(|filter| filter(msg))
+    }
+}
+
+/// Subscribers of a topic that receive each message in turn
+struct Group<Msg: Message> {
+    members: Vec<BoxedTell<Msg>>,
+    next: Cell<usize>,
+}
+
+impl<Msg: Message> Group<Msg> {
+    fn next(&self) -> Option<&BoxedTell<Msg>> {
+        if self.members.is_empty() {
+            return None;
+        }
+
+        let member = self.next.get() % self.members.len();
+        self.next.set(member + 1);
+        self.members.get(member)
+    }
+}
 
-/// A specialized actor for providing Publish/Subscribe capabilities to users.
+/// The subscriptions of a channel, indexed by the segments of their topics
 ///
+/// Each node holds the subscriptions to the topic made of the segments
+/// on the path from the root, so a published topic is matched by walking
+/// its segments, following the `*` and `#` wildcard nodes on the way.
+struct Subscriptions<Msg: Message> {
+    subs: Vec<Subscriber<Msg>>,
+    groups: HashMap<String, Group<Msg>>,
+    children: HashMap<String, Self>,
+}
+
+impl<Msg: Message> Default for Subscriptions<Msg> {
+    fn default() -> Self {
+        Self {
+            subs: Vec::new(),
+            groups: HashMap::new(),
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl<Msg: Message> Subscriptions<Msg> {
+    fn node_mut(&mut self, topic: &Topic) -> &mut Self {
+        topic.segments().into_iter().fold(self, |node, segment| {
+            node.children.entry(segment.to_string()).or_default()
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.subs.is_empty() && self.groups.is_empty() && self.children.is_empty()
+    }
+
+    /// The nodes with subscriptions matching the remaining segments of a topic
+    fn collect<'a>(&'a self, segments: &[&str], found: &mut Vec<&'a Self>) {
+        if let Some(any) = self.children.get("#") {
+            for i in 0..=segments.len() {
+                any.collect(&segments[i..], found);
+            }
+        }
+
+        match segments.split_first() {
+            None => {
+                if !found.iter().any(|node| ptr::eq(*node, self)) {
+                    found.push(self);
+                }
+            }
+            Some((segment, rest)) => {
+                if let Some(child) = self.children.get(*segment) {
+                    child.collect(rest, found);
+                }
+                if *segment != "*" {
+                    if let Some(child) = self.children.get("*") {
+                        child.collect(rest, found);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The actors subscribed to the topic that accept the message and
+    /// the next member of each group subscribed to the topic
+    ///
+    /// Each subscriber is included once, even if several of its subscriptions
+    /// match the topic. An actor subscribed both directly and through a
+    /// `contramap` conversion is a subscriber for each.
+    fn subscribers(&self, topic: &Topic, msg: &Msg) -> Vec<&BoxedTell<Msg>> {
+        let mut nodes = Vec::new();
+        self.collect(&topic.segments(), &mut nodes);
+
+        let mut seen = HashSet::new();
+        let mut subscribers = Vec::new();
+        for node in nodes {
+            let subs = node.subs.iter().filter(|sub| sub.accepts(msg));
+            let members = node.groups.values().filter_map(Group::next);
+
+            for actor in subs.map(|sub| &sub.actor).chain(members) {
+                if seen.insert((actor.path(), actor.conversion())) {
+                    subscribers.push(actor);
+                }
+            }
+        }
+        subscribers
+    }
+
+    /// Remove the subscriber from the subscriptions to the remaining segments of a topic
+    fn unsubscribe(&mut self, segments: &[&str], actor: &BoxedTell<Msg>) {
+        match segments.split_first() {
+            None => self.remove(&|sub| sub == actor),
+            Some((segment, rest)) => {
+                if let Some(child) = self.children.get_mut(*segment) {
+                    child.unsubscribe(rest, actor);
+                    if child.is_empty() {
+                        self.children.remove(*segment);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the matching subscribers from the subscriptions to every topic
+    fn unsubscribe_all(&mut self, matches: &dyn Fn(&BoxedTell<Msg>) -> bool) {
+        self.remove(matches);
+        for child in self.children.values_mut() {
+            child.unsubscribe_all(matches);
+        }
+        self.children.retain(|_, child| !child.is_empty());
+    }
+
+    fn remove(&mut self, matches: &dyn Fn(&BoxedTell<Msg>) -> bool) {
+        self.subs.retain(|sub| !matches(&sub.actor));
+
+        for group in self.groups.values_mut() {
+            group.members.retain(|m| !matches(m));
+        }
+        self.groups.retain(|_, group| !group.members.is_empty());
+    }
+}
+
+// A specialized actor for providing Publish/Subscribe capabilities to users.
 
 // Generic Channel
 pub type ChannelCtx<Msg> = Context<ChannelMsg<Msg>>;
@@ -20,32 +174,125 @@ pub type ChannelRef<Msg> = ActorRef<ChannelMsg<Msg>>;
 
 /// A specialized actor for providing Publish/Subscribe capabilities for user level messages
 pub struct Channel<Msg: Message> {
-    subs: Subs<Msg>,
+    subs: Subscriptions<Msg>,
+    retention: Retention,
+    retained: HashMap<Topic, VecDeque<(Instant, Msg)>>,
+}
+
+impl<Msg: Message> Channel<Msg> {
+    /// Keep a published message to replay to future subscribers
+    fn retain(&mut self, topic: &Topic, msg: &Msg) {
+        let limit = match self.retention {
+            Retention::None => return,
+            Retention::LastValue => Some(1),
+            Retention::Last(count) => Some(count),
+            Retention::Within(_) => None,
+        };
+
+        let retained = self.retained.entry(topic.clone()).or_default();
+        retained.push_back((Instant::now(), msg.clone()));
+        if let Some(limit) = limit {
+            while retained.len() > limit {
+                retained.pop_front();
+            }
+        }
+        self.expire();
+    }
+
+    /// Drop the retained messages older than the retention duration
+    fn expire(&mut self) {
+        if let Retention::Within(duration) = self.retention {
+            for retained in self.retained.values_mut() {
+                while let Some((at, _)) = retained.front() {
+                    if at.elapsed() <= duration {
+                        break;
+                    }
+                    retained.pop_front();
+                }
+            }
+            self.retained.retain(|_, retained| !retained.is_empty());
+        }
+    }
+
+    fn subscribe(&mut self, topic: &Topic, sub: Subscriber<Msg>) {
+        for retained in self.replay(topic) {
+            if sub.accepts(&retained) {
+                sub.actor.tell(retained, None);
+            }
+        }
+        self.subs.node_mut(topic).subs.push(sub);
+    }
+
+    /// The retained messages of the topics matching a subscription,
+    /// in the order they were published
+    fn replay(&mut self, topic: &Topic) -> Vec<Msg> {
+        self.expire();
+
+        let mut retained: Vec<&(Instant, Msg)> = self
+            .retained
+            .iter()
+            .filter(|(t, _)| topic.matches(t))
+            .flat_map(|(_, retained)| retained.iter())
+            .collect();
+        retained.sort_by_key(|(at, _)| *at);
+        retained.into_iter().map(|(_, msg)| msg.clone()).collect()
+    }
 }
 
 impl<Msg: Message> Default for Channel<Msg> {
     fn default() -> Self {
+        Self::create_args(Retention::None)
+    }
+}
+
+impl<Msg: Message> ActorFactoryArgs<Retention> for Channel<Msg> {
+    fn create_args(retention: Retention) -> Self {
         Self {
-            subs: HashMap::new(),
+            subs: Subscriptions::default(),
+            retention,
+            retained: HashMap::new(),
         }
     }
 }
 
+/// The messages a channel keeps of each topic to replay to new subscribers
+///
+/// When an actor subscribes, the retained messages of the topics matching
+/// its subscription are sent to it before any newly published message.
+/// Messages are not replayed to actors subscribing as members of a group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Retention {
+    /// Published messages are not retained
+    #[default]
+    None,
+
+    /// The last message published to each topic is retained
+    LastValue,
+
+    /// The last `n` messages published to each topic are retained
+    Last(usize),
+
+    /// The messages published to each topic within the duration are retained
+    Within(Duration),
+}
+
 impl<Msg> Actor for Channel<Msg>
 where
     Msg: Message,
 {
     type Msg = ChannelMsg<Msg>;
 
-    // todo subscribe to events to unsub subscribers when they die
-    fn pre_start(&mut self, _ctx: &ChannelCtx<Msg>) {
-        // let sub = Subscribe {
-        //     topic: SysTopic::ActorTerminated.into(),
-        //     actor: Box::new(ctx.myself.clone())//.into()
-        // };
-
-        // let msg = ChannelMsg::Subscribe(sub);
-        // ctx.myself.tell(msg, None);
+    // Subscribe to ActorTerminated events to unsubscribe subscribers when
+    // they terminate. The system channels are started before the system
+    // events channel is available and are subscribed when the system starts.
+    fn pre_start(&mut self, ctx: &ChannelCtx<Msg>) {
+        if let Some(ref channels) = ctx.system.sys_channels {
+            let sub = Subscribe {
+                topic: SysTopic::ActorTerminated.into(),
+                actor: Box::new(BasicActorRef::from(ctx.myself())),
+            };
+            channels.sys_events.tell(sub, None);
+        }
     }
 
     fn recv(&mut self, ctx: &ChannelCtx<Msg>, msg: ChannelMsg<Msg>, sender: Sender) {
@@ -56,14 +303,9 @@ where
     // to this system event. This allows us to remove actors that have been
     // terminated but did not explicity unsubscribe before terminating.
     fn sys_recv(&mut self, _: &ChannelCtx<Msg>, msg: SystemMsg, _sender: Sender) {
-        if let SystemMsg::Event(evt) = msg {
-            if let SystemEvent::ActorTerminated(terminated) = evt {
-                let subs = self.subs.clone();
-
-                for topic in subs.keys() {
-                    unsubscribe(&mut self.subs, topic, &terminated.actor);
-                }
-            }
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            let path = terminated.actor.path();
+            self.subs.unsubscribe_all(&|sub| sub.path() == path);
         }
     }
 }
@@ -78,6 +320,8 @@ where
         match msg {
             ChannelMsg::Publish(p) => self.receive(ctx, p, sender),
             ChannelMsg::Subscribe(sub) => self.receive(ctx, sub, sender),
+            ChannelMsg::SubscribeGroup(sub) => self.receive(ctx, sub, sender),
+            ChannelMsg::SubscribeFiltered(sub) => self.receive(ctx, sub, sender),
             ChannelMsg::Unsubscribe(unsub) => self.receive(ctx, unsub, sender),
             ChannelMsg::UnsubscribeAll(unsub) => self.receive(ctx, unsub, sender),
         }
@@ -91,8 +335,46 @@ where
     type Msg = ChannelMsg<Msg>;
 
     fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: Subscribe<Msg>, _sender: Sender) {
-        let subs = self.subs.entry(msg.topic).or_default();
-        subs.push(msg.actor);
+        let sub = Subscriber {
+            actor: msg.actor,
+            filter: None,
+        };
+        self.subscribe(&msg.topic, sub);
+    }
+}
+
+impl<Msg> Receive<SubscribeFiltered<Msg>> for Channel<Msg>
+where
+    Msg: Message,
+{
+    type Msg = ChannelMsg<Msg>;
+
+    fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: SubscribeFiltered<Msg>, _sender: Sender) {
+        let sub = Subscriber {
+            actor: msg.actor,
+            filter: Some(msg.filter),
+        };
+        self.subscribe(&msg.topic, sub);
+    }
+}
+
+impl<Msg> Receive<SubscribeGroup<Msg>> for Channel<Msg>
+where
+    Msg: Message,
+{
+    type Msg = ChannelMsg<Msg>;
+
+    fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: SubscribeGroup<Msg>, _sender: Sender) {
+        let group = self
+            .subs
+            .node_mut(&msg.topic)
+            .groups
+            .entry(msg.group)
+            .or_insert_with(|| Group {
+                members: Vec::new(),
+                next: Cell::new(0),
+            });
+        group.members.push(msg.actor);
     }
 }
 
@@ -103,7 +385,7 @@ where
     type Msg = ChannelMsg<Msg>;
 
     fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: Unsubscribe<Msg>, _sender: Sender) {
-        unsubscribe(&mut self.subs, &msg.topic, &msg.actor);
+        self.subs.unsubscribe(&msg.topic.segments(), &msg.actor);
     }
 }
 
@@ -114,11 +396,7 @@ where
     type Msg = ChannelMsg<Msg>;
 
     fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: UnsubscribeAll<Msg>, _sender: Sender) {
-        let subs = self.subs.clone();
-
-        for topic in subs.keys() {
-            unsubscribe(&mut self.subs, topic, &msg.actor);
-        }
+        self.subs.unsubscribe_all(&|sub| sub == &msg.actor);
     }
 }
 
@@ -129,32 +407,17 @@ where
     type Msg = ChannelMsg<Msg>;
 
     fn receive(&mut self, _ctx: &ChannelCtx<Msg>, msg: Publish<Msg>, sender: Sender) {
-        // send system event to actors subscribed to all topics
-        if let Some(subs) = self.subs.get(&All.into()) {
-            for sub in subs.iter() {
-                sub.tell(msg.msg.clone(), sender.clone());
-            }
-        }
-
-        // send system event to actors subscribed to the topic
-        if let Some(subs) = self.subs.get(&msg.topic) {
-            for sub in subs.iter() {
+        self.retain(&msg.topic, &msg.msg);
+
+        // send to actors subscribed to a matching topic and to one actor
+        // of each group subscribed to a matching topic. The last subscriber
+        // is sent the published message itself instead of a clone.
+        let subs = self.subs.subscribers(&msg.topic, &msg.msg);
+        if let Some((last, subs)) = subs.split_last() {
+            for sub in subs {
                 sub.tell(msg.msg.clone(), sender.clone());
             }
-        }
-    }
-}
-
-fn unsubscribe<Msg>(subs: &mut Subs<Msg>, topic: &Topic, actor: &dyn ActorReference) {
-    // Nightly only: self.subs.get(msg_type).unwrap().remove_item(actor);
-    if subs.contains_key(topic) {
-        if let Some(pos) = subs
-            .get(topic)
-            .unwrap()
-            .iter()
-            .position(|x| x.path() == actor.path())
-        {
-            subs.get_mut(topic).unwrap().remove(pos);
+            last.tell(msg.msg, sender);
         }
     }
 }
@@ -193,6 +456,8 @@ impl Receive<ChannelMsg<SystemEvent>> for EventsChannel {
         match msg {
             ChannelMsg::Publish(p) => self.receive(ctx, p, sender),
             ChannelMsg::Subscribe(sub) => self.0.receive(ctx, sub, sender),
+            ChannelMsg::SubscribeGroup(sub) => self.0.receive(ctx, sub, sender),
+            ChannelMsg::SubscribeFiltered(sub) => self.0.receive(ctx, sub, sender),
             ChannelMsg::Unsubscribe(unsub) => self.0.receive(ctx, unsub, sender),
             ChannelMsg::UnsubscribeAll(unsub) => self.0.receive(ctx, unsub, sender),
         }
@@ -208,20 +473,15 @@ impl Receive<Publish<SystemEvent>> for EventsChannel {
         msg: Publish<SystemEvent>,
         _sender: Sender,
     ) {
-        // send system event to actors subscribed to all topics
-        if let Some(subs) = self.0.subs.get(&All.into()) {
-            for sub in subs.iter() {
-                let evt = SystemMsg::Event(msg.msg.clone());
-                sub.sys_tell(evt);
-            }
-        }
-
-        // send system event to actors subscribed to the topic
-        if let Some(subs) = self.0.subs.get(&msg.topic) {
-            for sub in subs.iter() {
+        // send system event to actors subscribed to a matching topic and
+        // to one actor of each group subscribed to a matching topic
+        let subs = self.0.subs.subscribers(&msg.topic, &msg.msg);
+        if let Some((last, subs)) = subs.split_last() {
+            for sub in subs {
                 let evt = SystemMsg::Event(msg.msg.clone());
                 sub.sys_tell(evt);
             }
+            last.sys_tell(SystemMsg::Event(msg.msg));
         }
     }
 }
@@ -242,6 +502,40 @@ pub struct Subscribe<Msg: Message> {
     pub actor: BoxedTell<Msg>,
 }
 
+/// Subscribe an actor to a topic as a member of a group
+///
+/// Each message published to the topic is sent to only one member of
+/// the group, in turn, instead of to every member.
+#[derive(Debug, Clone)]
+pub struct SubscribeGroup<Msg: Message> {
+    pub topic: Topic,
+    pub group: String,
+    pub actor: BoxedTell<Msg>,
+}
+
+/// A predicate on the messages published to a topic
+pub type Filter<Msg> = Arc<dyn Fn(&Msg) -> bool + Send + Sync>;
+
+/// Subscribe an actor to the messages published to a topic that match a filter
+///
+/// The filter is evaluated by the channel, so the actor isn't sent the
+/// messages it isn't interested in.
+#[derive(Clone)]
+pub struct SubscribeFiltered<Msg: Message> {
+    pub topic: Topic,
+    pub actor: BoxedTell<Msg>,
+    pub filter: Filter<Msg>,
+}
+
+impl<Msg: Message> fmt::Debug for SubscribeFiltered<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscribeFiltered")
+            .field("topic", &self.topic)
+            .field("actor", &self.actor)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Unsubscribe<Msg: Message> {
     pub topic: Topic,
@@ -267,6 +561,12 @@ pub enum ChannelMsg<Msg: Message> {
     /// Subscribe given `ActorRef` to a topic on a channel
     Subscribe(Subscribe<Msg>),
 
+    /// Subscribe given `ActorRef` to a topic on a channel as a member of a group
+    SubscribeGroup(SubscribeGroup<Msg>),
+
+    /// Subscribe given `ActorRef` to the messages of a topic on a channel that match a filter
+    SubscribeFiltered(SubscribeFiltered<Msg>),
+
     /// Unsubscribe the given `ActorRef` from a topic on a channel
     Unsubscribe(Unsubscribe<Msg>),
 
@@ -288,6 +588,20 @@ impl<Msg: Message> Into<ChannelMsg<Msg>> for Subscribe<Msg> {
     }
 }
 
+// subscribe group
+impl<Msg: Message> Into<ChannelMsg<Msg>> for SubscribeGroup<Msg> {
+    fn into(self) -> ChannelMsg<Msg> {
+        ChannelMsg::SubscribeGroup(self)
+    }
+}
+
+// subscribe filtered
+impl<Msg: Message> Into<ChannelMsg<Msg>> for SubscribeFiltered<Msg> {
+    fn into(self) -> ChannelMsg<Msg> {
+        ChannelMsg::SubscribeFiltered(self)
+    }
+}
+
 // unsubscribe
 impl<Msg: Message> Into<ChannelMsg<Msg>> for Unsubscribe<Msg> {
     fn into(self) -> ChannelMsg<Msg> {
@@ -305,9 +619,42 @@ impl<Msg: Message> Into<ChannelMsg<Msg>> for UnsubscribeAll<Msg> {
 // Topics allow channel subscribers to filter messages by interest
 ///
 /// When publishing a message to a channel a Topic is provided.
+///
+/// Topics are hierarchical, with segments separated by `.`. A topic
+/// subscribed to can contain wildcards: `*` matches exactly one segment
+/// and `#` matches zero or more segments, so `orders.*.created` matches
+/// `orders.eu.created` and `orders.#` matches `orders`, `orders.eu` and
+/// `orders.eu.created`. The topic `*` on its own (`All`) matches every topic.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Topic(String);
 
+impl Topic {
+    /// Whether a message published to `topic` matches this topic
+    #[must_use]
+    pub fn matches(&self, topic: &Self) -> bool {
+        matches_segments(&self.segments(), &topic.segments())
+    }
+
+    fn segments(&self) -> Vec<&str> {
+        if self.0 == "*" {
+            vec!["#"]
+        } else {
+            self.0.split('.').collect()
+        }
+    }
+}
+
+fn matches_segments(pattern: &[&str], topic: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => topic.is_empty(),
+        Some((&"#", rest)) => (0..=topic.len()).any(|i| matches_segments(rest, &topic[i..])),
+        Some((segment, rest)) => match topic.split_first() {
+            Some((t, topic)) => (*segment == "*" || segment == t) && matches_segments(rest, topic),
+            None => false,
+        },
+    }
+}
+
 impl<'a> From<&'a str> for Topic {
     fn from(topic: &str) -> Self {
         Self(topic.to_string())
@@ -320,6 +667,12 @@ impl From<String> for Topic {
     }
 }
 
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 impl<'a> From<&'a SystemEvent> for Topic {
     fn from(evt: &SystemEvent) -> Self {
         match *evt {
@@ -362,3 +715,15 @@ where
 {
     fact.actor_of::<Channel<Msg>>(name)
 }
+
+/// Create a channel that retains published messages to replay to new subscribers
+pub fn channel_with_retention<Msg>(
+    name: &str,
+    retention: Retention,
+    fact: &impl ActorRefFactory,
+) -> Result<ChannelRef<Msg>, CreateError>
+where
+    Msg: Message,
+{
+    fact.actor_of_args::<Channel<Msg>, _>(name, retention)
+}
@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use config::Config;
+use slog::warn;
+use uuid::Uuid;
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, ActorReference, Sender, Tell};
+use crate::{
+    actor::{Actor, ActorFactoryArgs, Context, CreateError},
+    system::{ActorSystem, SystemEvent, SystemMsg, Timer},
+    Message,
+};
+
+pub type EntityManagerRef<Msg> = ActorRef<EntityManagerMsg<Msg>>;
+
+/// A specialized actor that manages entity actors by entity id.
+///
+/// Messages are sent to an entity by telling the manager an `EntityMsg`.
+/// If no actor exists for the entity id the manager creates one as its
+/// child, passing the entity id to `ActorFactoryArgs::create_args`.
+///
+/// An entity actor that has received no messages for the duration set
+/// by `cqrs.sleep_after_secs` is passivated, i.e. stopped. The next
+/// message addressed to its entity id transparently creates the actor
+/// again. Persistent entity actors recover their state when recreated.
+///
+/// Entity ids can contain any character. They are encoded into valid
+/// actor names, e.g. the entity actor of `acc/1` is named `acc_2f1`.
+pub struct EntityManager<A: Actor> {
+    entities: HashMap<String, Entity<A::Msg>>,
+    sleep_after: Duration,
+    schedule: Option<(Uuid, ActorSystem)>,
+    actor: PhantomData<A>,
+}
+
+struct Entity<Msg: Message> {
+    actor: Option<ActorRef<Msg>>,
+    last_msg: Instant,
+    passivating: bool,
+    stash: Vec<(Msg, Sender)>,
+}
+
+impl<A: Actor> Default for EntityManager<A> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::new(),
+            // set from `cqrs.sleep_after_secs` in pre_start
+            sleep_after: Duration::default(),
+            schedule: None,
+            actor: PhantomData,
+        }
+    }
+}
+
+impl<A> EntityManager<A>
+where
+    A: ActorFactoryArgs<String>,
+{
+    #[allow(clippy::unused_self)]
+    fn create(
+        &self,
+        ctx: &Context<EntityManagerMsg<A::Msg>>,
+        id: &str,
+    ) -> Option<ActorRef<A::Msg>> {
+        match ctx.actor_of_args::<A, _>(&entity_name(id), id.to_string()) {
+            Ok(actor) => Some(actor),
+            Err(e) => {
+                warn!(ctx.system.log(), "Failed to create entity actor: {}", e);
+                None
+            }
+        }
+    }
+
+    fn deliver(
+        &mut self,
+        ctx: &Context<EntityManagerMsg<A::Msg>>,
+        msg: EntityMsg<A::Msg>,
+        sender: Sender,
+    ) {
+        if let Some(entity) = self.entities.get_mut(&msg.id) {
+            if entity.passivating {
+                // the actor is stopping. Its messages are delivered once
+                // it has terminated and has been created again
+                entity.stash.push((msg.msg, sender));
+            } else if let Some(ref actor) = entity.actor {
+                entity.last_msg = Instant::now();
+                actor.send_msg(msg.msg, sender);
+            }
+        } else if let Some(actor) = self.create(ctx, &msg.id) {
+            actor.send_msg(msg.msg, sender);
+            self.entities.insert(
+                msg.id,
+                Entity {
+                    actor: Some(actor),
+                    last_msg: Instant::now(),
+                    passivating: false,
+                    stash: Vec::new(),
+                },
+            );
+        }
+    }
+
+    fn passivate_idle(&mut self, ctx: &Context<EntityManagerMsg<A::Msg>>) {
+        let sleep_after = self.sleep_after;
+
+        for entity in self.entities.values_mut() {
+            if !entity.passivating && entity.last_msg.elapsed() >= sleep_after {
+                if let Some(ref actor) = entity.actor {
+                    ctx.stop(actor);
+                    entity.passivating = true;
+                }
+            }
+        }
+    }
+
+    fn recreate(&mut self, ctx: &Context<EntityManagerMsg<A::Msg>>, id: &str) {
+        let actor = self.create(ctx, id);
+
+        if let Some(entity) = self.entities.get_mut(id) {
+            if let Some(ref actor) = actor {
+                for (msg, sender) in entity.stash.drain(..) {
+                    actor.send_msg(msg, sender);
+                }
+            }
+
+            entity.actor = actor;
+            entity.last_msg = Instant::now();
+            entity.passivating = false;
+        }
+    }
+}
+
+impl<A> Actor for EntityManager<A>
+where
+    A: ActorFactoryArgs<String>,
+{
+    type Msg = EntityManagerMsg<A::Msg>;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.sleep_after = EntityManagerConfig::from(&ctx.system.config()).sleep_after;
+
+        // check for idle entities several times within the sleep duration
+        let interval = std::cmp::max(self.sleep_after / 4, Duration::from_millis(100));
+        let id = ctx.schedule(
+            interval,
+            interval,
+            ctx.myself(),
+            None,
+            EntityManagerMsg::PassivateIdle,
+        );
+        self.schedule = Some((id, ctx.system.clone()));
+    }
+
+    fn post_stop(&mut self) {
+        if let Some((id, sys)) = self.schedule.take() {
+            sys.cancel_schedule(id);
+        }
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        match msg {
+            EntityManagerMsg::Tell(msg) => self.deliver(ctx, msg, sender),
+            EntityManagerMsg::PassivateIdle => self.passivate_idle(ctx),
+            EntityManagerMsg::Recreate(id) => self.recreate(ctx, &id),
+        }
+    }
+
+    // Entity actors are children of the manager, so the manager
+    // receives ActorTerminated when an entity actor stops.
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            let Some(id) = entity_id(terminated.actor.name()) else {
+                return;
+            };
+
+            let recreate = match self.entities.get_mut(&id) {
+                Some(entity) if !entity.stash.is_empty() => {
+                    entity.actor = None;
+                    true
+                }
+                Some(_) => {
+                    self.entities.remove(&id);
+                    false
+                }
+                None => false,
+            };
+
+            // the terminated actor is removed from the manager's children
+            // after this function returns, so creating the actor again
+            // is deferred to the next message.
+            if recreate {
+                ctx.myself.tell(EntityManagerMsg::Recreate(id), None);
+            }
+        }
+    }
+}
+
+/// A message addressed to the entity actor with the entity id `id`
+#[derive(Clone, Debug)]
+pub struct EntityMsg<Msg: Message> {
+    pub id: String,
+    pub msg: Msg,
+}
+
+impl<Msg: Message> EntityMsg<Msg> {
+    pub fn new<T: Into<Msg>>(id: &str, msg: T) -> Self {
+        Self {
+            id: id.to_string(),
+            msg: msg.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EntityManagerMsg<Msg: Message> {
+    /// Deliver a message to an entity actor
+    Tell(EntityMsg<Msg>),
+
+    /// Passivate the entity actors that have been idle
+    PassivateIdle,
+
+    /// Create a passivated entity actor that has pending messages
+    Recreate(String),
+}
+
+impl<Msg: Message> Into<EntityManagerMsg<Msg>> for EntityMsg<Msg> {
+    fn into(self) -> EntityManagerMsg<Msg> {
+        EntityManagerMsg::Tell(self)
+    }
+}
+
+/// Encodes an entity id into a valid actor name.
+///
+/// ASCII letters, digits and `-` are kept, any other byte is written as
+/// `_` followed by two hex digits. An empty id is named `_`.
+fn entity_name(id: &str) -> String {
+    if id.is_empty() {
+        return "_".to_string();
+    }
+
+    let mut name = String::with_capacity(id.len());
+    for b in id.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' {
+            name.push(char::from(b));
+        } else {
+            let _ = write!(name, "_{:02x}", b);
+        }
+    }
+    name
+}
+
+/// Decodes the entity id from the name of an entity actor
+fn entity_id(name: &str) -> Option<String> {
+    if name == "_" {
+        return Some(String::new());
+    }
+
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut chars = name.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'_' {
+            let hex = [chars.next()?, chars.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+struct EntityManagerConfig {
+    sleep_after: Duration,
+}
+
+impl<'a> From<&'a Config> for EntityManagerConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            sleep_after: Duration::from_secs(config.get::<u64>("cqrs.sleep_after_secs").unwrap()),
+        }
+    }
+}
+
+pub fn entity_manager<A>(
+    name: &str,
+    fact: &impl ActorRefFactory,
+) -> Result<EntityManagerRef<A::Msg>, CreateError>
+where
+    A: ActorFactoryArgs<String>,
+{
+    fact.actor_of::<EntityManager<A>>(name)
+}
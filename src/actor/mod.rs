@@ -1,6 +1,8 @@
 pub(crate) mod actor_cell;
 pub(crate) mod actor_ref;
+pub(crate) mod async_actor;
 pub(crate) mod channel;
+pub(crate) mod entity;
 pub(crate) mod macros;
 pub(crate) mod props;
 pub(crate) mod selection;
@@ -8,23 +10,28 @@ pub(crate) mod uri;
 
 use std::fmt;
 
+use futures::future::BoxFuture;
+
 use crate::validate::InvalidName;
 
 // Public riker::actor API (plus the pub data types in this file)
 pub use self::{
     actor_cell::Context,
+    async_actor::{Async, AsyncActor},
     channel::{
-        channel, All, Channel, ChannelMsg, ChannelRef, DLChannelMsg, DeadLetter, EventsChannel,
-        Publish, Subscribe, SysTopic, Topic, Unsubscribe, UnsubscribeAll,
+        channel, channel_with_retention, All, Channel, ChannelMsg, ChannelRef, DLChannelMsg,
+        DeadLetter, EventsChannel, Filter, Publish, Retention, Subscribe, SubscribeFiltered,
+        SubscribeGroup, SysTopic, Topic, Unsubscribe, UnsubscribeAll,
     },
+    entity::{entity_manager, EntityManager, EntityManagerMsg, EntityManagerRef, EntityMsg},
     macros::actor,
     props::{ActorArgs, ActorFactory, ActorFactoryArgs, ActorProducer, BoxActorProd, Props},
-    selection::{ActorSelection, ActorSelectionFactory},
-    uri::{ActorId, ActorPath, ActorUri},
+    selection::{ActorSelection, ActorSelectionFactory, ResolveError},
+    uri::{ActorAddress, ActorId, ActorPath, ActorUri, InvalidAddress},
 };
 use crate::actor_ref::BasicActorRef;
 use crate::actor_ref::Sender;
-use crate::system::SystemMsg;
+use crate::system::{PersistenceConf, Snapshot, SystemMsg};
 use crate::Message;
 
 pub type MsgResult<T> = Result<(), MsgError<T>>;
@@ -144,11 +151,45 @@ pub trait Actor: Send + 'static {
     /// at any one time, including `recv` and `sys_recv`.
     fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender);
 
+    /// Invoked by the kernel to handle a message
+    ///
+    /// Returns the future handling the message, which the kernel awaits
+    /// before handling the next message, or `None` if it has been
+    /// handled by `recv`. Implemented by `Async` to run an `AsyncActor`.
+    #[doc(hidden)]
+    fn recv_future<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> Option<BoxFuture<'a, ()>> {
+        self.recv(ctx, msg, sender);
+        None
+    }
+
     /// Invoked when an actor receives a system message
     ///
     /// It is guaranteed that only one message in the actor's mailbox is processed
     /// at any one time, including `recv` and `sys_recv`.
     fn sys_recv(&mut self, _ctx: &Context<Self::Msg>, _msg: SystemMsg, _sender: Sender) {}
+
+    /// Return a persistence configuration to make this a persistent actor.
+    ///
+    /// Persistent actors recover their state from the latest snapshot
+    /// and the events persisted after it when they start or restart.
+    fn persistence_conf(&self) -> Option<PersistenceConf> {
+        None
+    }
+
+    /// Invoked with the latest snapshot when a persistent actor is recovering.
+    ///
+    /// Only events persisted after the snapshot are replayed.
+    fn recover_snapshot(&mut self, _ctx: &Context<Self::Msg>, _snapshot: Snapshot) {}
+
+    /// Invoked for each persisted event when a persistent actor is recovering.
+    ///
+    /// It is *not* safe to create side effects in `replay_event`.
+    fn replay_event(&mut self, _ctx: &Context<Self::Msg>, _evt: Self::Msg) {}
 }
 
 impl<A: Actor + ?Sized> Actor for Box<A> {
@@ -174,6 +215,15 @@ impl<A: Actor + ?Sized> Actor for Box<A> {
         (**self).recv(ctx, msg, sender)
     }
 
+    fn recv_future<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> Option<BoxFuture<'a, ()>> {
+        (**self).recv_future(ctx, msg, sender)
+    }
+
     fn sys_recv(
         &mut self,
         ctx: &Context<Self::Msg>,
@@ -182,6 +232,18 @@ impl<A: Actor + ?Sized> Actor for Box<A> {
     ) {
         (**self).sys_recv(ctx, msg, sender)
     }
+
+    fn persistence_conf(&self) -> Option<PersistenceConf> {
+        (**self).persistence_conf()
+    }
+
+    fn recover_snapshot(&mut self, ctx: &Context<Self::Msg>, snapshot: Snapshot) {
+        (**self).recover_snapshot(ctx, snapshot);
+    }
+
+    fn replay_event(&mut self, ctx: &Context<Self::Msg>, evt: Self::Msg) {
+        (**self).replay_event(ctx, evt);
+    }
 }
 
 /// Receive and handle a specific message type
@@ -1,8 +1,9 @@
-use std::iter::Peekable;
+use std::{collections::HashSet, fmt};
 
-use crate::actor_ref::{ActorReference, BasicActorRef};
+use regex::Regex;
+
+use crate::actor_ref::{ActorRef, ActorReference, BasicActorRef};
 use crate::{
-    actor::Sender,
     system::SystemMsg,
     validate::{validate_path, InvalidPath},
     Message,
@@ -32,6 +33,9 @@ use crate::{
 /// Since a selection is a collection of `BasicActorRef`s messaging is
 /// un-typed. Messages not supported by any actor in the selection will
 /// be dropped.
+///
+/// `selection.resolve()` returns the actors currently in the selection,
+/// and `selection.resolve_one_typed()` an `ActorRef` that can be kept.
 #[derive(Debug)]
 pub struct ActorSelection {
     anchor: BasicActorRef,
@@ -48,16 +52,17 @@ impl ActorSelection {
     ) -> Result<Self, InvalidPath> {
         validate_path(&path)?;
 
-        let path_vec: Vec<Selection> = path
+        let path_vec = path
             .split_terminator('/')
-            .map({
-                |seg| match seg {
-                    ".." => Selection::Parent,
-                    "*" => Selection::AllChildren,
-                    name => Selection::ChildName(name.to_string()),
-                }
+            .map(|seg| match seg {
+                ".." => Ok(Selection::Parent),
+                "*" => Ok(Selection::AllChildren),
+                "**" => Ok(Selection::Descendants),
+                name if name.contains(&['*', '?', '['][..]) => glob(name).map(Selection::Pattern),
+                name => Ok(Selection::ChildName(name.to_string())),
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| InvalidPath::new(&path))?;
 
         Ok(Self {
             anchor,
@@ -71,124 +76,93 @@ impl ActorSelection {
     where
         Msg: Message,
     {
-        fn walk<'a, I, Msg>(
-            anchor: &BasicActorRef,
-            // dl: &BasicActorRef,
-            mut path_vec: Peekable<I>,
-            msg: Msg,
-            sender: &Sender,
-            path: &str,
-        ) where
-            I: Iterator<Item = &'a Selection>,
-            Msg: Message,
-        {
-            let seg = path_vec.next();
-
-            match seg {
-                Some(&Selection::Parent) => {
-                    if path_vec.peek().is_none() {
-                        let parent = anchor.parent();
-                        let _ = parent.try_tell(msg, sender.clone());
-                    } else {
-                        walk(&anchor.parent(), path_vec, msg, sender, path);
-                    }
-                }
-                Some(&Selection::AllChildren) => {
-                    for child in anchor.children() {
-                        let _ = child.try_tell(msg.clone(), sender.clone());
-                    }
-                }
-                Some(&Selection::ChildName(ref name)) => {
-                    let child = anchor.children().filter({ |c| c.name() == name }).last();
-                    if path_vec.peek().is_none() {
-                        if let Some(actor_ref) = child {
-                            actor_ref.try_tell(msg, sender.clone()).unwrap();
-                        }
-                    } else if path_vec.peek().is_some() && child.is_some() {
-                        walk(
-                            child.as_ref().unwrap(),
-                            // dl,
-                            path_vec,
-                            msg,
-                            sender,
-                            path,
-                        );
-                    } else {
-                        // todo send to deadletters?
-                    }
-                }
-                None => {}
+        let sender = sender.into();
+        if let Some((last, actors)) = self.resolve().split_last() {
+            for actor in actors {
+                let _ = actor.try_tell(msg.clone(), sender.clone());
             }
+            let _ = last.try_tell(msg, sender);
         }
+    }
+
+    pub fn sys_tell(&self, msg: SystemMsg, _sender: impl Into<Option<BasicActorRef>>) {
+        if let Some((last, actors)) = self.resolve().split_last() {
+            for actor in actors {
+                actor.sys_tell(msg.clone());
+            }
+            last.sys_tell(msg);
+        }
+    }
 
-        walk(
-            &self.anchor,
-            // &self.dl,
-            self.path_vec.iter().peekable(),
-            msg,
-            &sender.into(),
-            &self.path,
-        );
+    /// The path the actors are selected by
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
     }
 
-    pub fn sys_tell(&self, msg: SystemMsg, sender: impl Into<Option<BasicActorRef>>) {
-        fn walk<'a, I>(
-            anchor: &BasicActorRef,
-            // dl: &BasicActorRef,
-            mut path_vec: Peekable<I>,
-            msg: SystemMsg,
-            sender: &Sender,
-            path: &str,
-        ) where
-            I: Iterator<Item = &'a Selection>,
-        {
-            let seg = path_vec.next();
-
-            match seg {
-                Some(&Selection::Parent) => {
-                    if path_vec.peek().is_none() {
-                        let parent = anchor.parent();
-                        parent.sys_tell(msg);
-                    } else {
-                        walk(&anchor.parent(), path_vec, msg, sender, path);
+    /// The actors currently in the selection
+    ///
+    /// The selection is resolved against the actor hierarchy at the time
+    /// of the call. Actors started later aren't included.
+    #[must_use]
+    pub fn resolve(&self) -> Vec<BasicActorRef> {
+        fn walk(anchor: &BasicActorRef, path: &[Selection], found: &mut Vec<BasicActorRef>) {
+            match path.split_first() {
+                None => found.push(anchor.clone()),
+                Some((Selection::Parent, rest)) => walk(&anchor.parent(), rest, found),
+                Some((Selection::ChildName(name), rest)) => {
+                    // todo send to deadletters if there's no child?
+                    if let Some(child) = anchor.children().find(|c| c.name() == name) {
+                        walk(&child, rest, found);
                     }
                 }
-                Some(&Selection::AllChildren) => {
+                Some((Selection::AllChildren, rest)) => {
                     for child in anchor.children() {
-                        child.sys_tell(msg.clone());
+                        walk(&child, rest, found);
                     }
                 }
-                Some(&Selection::ChildName(ref name)) => {
-                    let child = anchor.children().filter({ |c| c.name() == name }).last();
-                    if path_vec.peek().is_none() {
-                        if let Some(actor_ref) = child {
-                            actor_ref.try_tell(msg, sender.clone()).unwrap();
-                        }
-                    } else if path_vec.peek().is_some() && child.is_some() {
-                        walk(
-                            child.as_ref().unwrap(),
-                            // dl,
-                            path_vec,
-                            msg,
-                            sender,
-                            path,
-                        );
-                    } else {
-                        // todo send to deadletters?
+                Some((Selection::Pattern(pattern), rest)) => {
+                    for child in anchor.children().filter(|c| pattern.is_match(c.name())) {
+                        walk(&child, rest, found);
+                    }
+                }
+                Some((Selection::Descendants, rest)) => {
+                    walk(anchor, rest, found);
+                    for child in anchor.children() {
+                        walk(&child, path, found);
                     }
                 }
-                None => {}
             }
         }
 
-        walk(
-            &self.anchor,
-            // &self.dl,
-            self.path_vec.iter().peekable(),
-            msg,
-            &sender.into(),
-            &self.path,
-        );
+        let mut found = Vec::new();
+        walk(&self.anchor, &self.path_vec, &mut found);
+
+        // `**` can reach an actor more than once
+        let mut paths = HashSet::new();
+        found.retain(|actor| paths.insert(actor.path().clone()));
+        found
+    }
+
+    /// An actor in the selection
+    ///
+    /// If the selection contains several actors, the first one found is
+    /// returned.
+    pub fn resolve_one(&self) -> Result<BasicActorRef, ResolveError> {
+        self.resolve()
+            .into_iter()
+            .next()
+            .ok_or_else(|| ResolveError::NotFound(self.path.clone()))
+    }
+
+    /// An actor in the selection, as an `ActorRef` of its message type
+    ///
+    /// Fails if the selection is empty or if the actor found doesn't
+    /// accept messages of type `Msg`.
+    pub fn resolve_one_typed<Msg: Message>(&self) -> Result<ActorRef<Msg>, ResolveError> {
+        self.resolve_one()?
+            .typed_as()
+            .ok_or_else(|| ResolveError::UnexpectedType(self.path.clone()))
     }
 }
 
@@ -197,6 +171,63 @@ enum Selection {
     Parent,
     ChildName(String),
     AllChildren,
+    /// Children with a name matching a glob, e.g. `conn-*` or `node-[0-9]`
+    Pattern(Regex),
+    /// The actor and all its descendants, at any depth: `**`
+    Descendants,
+}
+
+/// Compile a glob of an actor name to a regex
+///
+/// `*` matches any characters, `?` matches one character and `[...]`
+/// matches one of a class of characters, or any other if it starts
+/// with `!` or `^`.
+fn glob(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut rgx = String::from("^");
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => rgx.push_str(".*"),
+            '?' => rgx.push('.'),
+            '[' => {
+                rgx.push('[');
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '!' | '^' if rgx.ends_with('[') => rgx.push('^'),
+                        c => rgx.push(c),
+                    }
+                }
+                rgx.push(']');
+            }
+            c => rgx.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    rgx.push('$');
+    Regex::new(&rgx)
+}
+
+/// Error type when a selection can't be resolved to an actor
+#[derive(Debug)]
+pub enum ResolveError {
+    NotFound(String),
+    UnexpectedType(String),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::NotFound(ref path) => f.write_str(&format!(
+                "Failed to resolve selection. Cause: No actor matches the path ({})",
+                path
+            )),
+            Self::UnexpectedType(ref path) => f.write_str(&format!(
+                "Failed to resolve selection. Cause: The actor doesn't accept the message type ({})",
+                path
+            )),
+        }
+    }
 }
 
 pub trait ActorSelectionFactory {
@@ -1,9 +1,12 @@
 use std::{
     fmt,
     hash::{Hash, Hasher},
+    str::FromStr,
     sync::Arc,
 };
 
+use crate::validate::{validate_actor_path, validate_name};
+
 pub type ActorId = usize;
 pub struct ActorPath(Arc<String>);
 
@@ -55,8 +58,8 @@ impl Clone for ActorPath {
 /// An `ActorUri` represents the location of an actor, including the
 /// path and actor system host.
 ///
-/// Note: `host` is currently unused but will be utilized when
-/// networking and clustering are introduced.
+/// For remote actor references `host` is the address of the
+/// remote actor system.
 #[derive(Clone)]
 pub struct ActorUri {
     pub uid: ActorId,
@@ -90,3 +93,122 @@ impl fmt::Debug for ActorUri {
         write!(f, "{}://{}#{}", self.host, self.path, self.uid)
     }
 }
+
+/// An `ActorAddress` is the full address of an actor, including the
+/// name and host of its actor system.
+///
+/// The canonical form is `riker://system@host:port/user/a/b`, where the
+/// port is only present for systems that use remoting. An address can be
+/// written to configuration files or logs and parsed again using `FromStr`:
+///
+/// ```ignore
+/// let addr: ActorAddress = "riker://my-app@10.0.0.1:7000/user/orders".parse()?;
+/// let orders = sys.select(&addr.to_string())?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ActorAddress {
+    pub system: String,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: ActorPath,
+}
+
+impl ActorAddress {
+    pub(crate) fn new(system: &str, host: &str, path: ActorPath) -> Self {
+        // the host of a system that uses remoting includes the port
+        let (host, port) = host.rfind(':').map_or((host, None), |i| {
+            host[i + 1..]
+                .parse::<u16>()
+                .map_or((host, None), |port| (&host[..i], Some(port)))
+        });
+
+        Self {
+            system: system.to_string(),
+            host: host.to_string(),
+            port,
+            path,
+        }
+    }
+
+    /// Returns `host:port`, or only the host if the address has no port
+    #[must_use]
+    pub fn authority(&self) -> String {
+        self.port.map_or_else(
+            || self.host.clone(),
+            |port| format!("{}:{}", self.host, port),
+        )
+    }
+}
+
+impl fmt::Display for ActorAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "riker://{}@{}{}",
+            self.system,
+            self.authority(),
+            self.path
+        )
+    }
+}
+
+impl FromStr for ActorAddress {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |cause: &str| InvalidAddress {
+            address: s.to_string(),
+            cause: cause.to_string(),
+        };
+
+        if !s.starts_with("riker://") {
+            return Err(invalid("Address must start with riker://"));
+        }
+
+        let rest = &s["riker://".len()..];
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => return Err(invalid("Missing actor path")),
+        };
+
+        let (system, host) = match authority.find('@') {
+            Some(i) => (&authority[..i], &authority[i + 1..]),
+            None => return Err(invalid("Missing actor system name")),
+        };
+
+        validate_name(system).map_err(|_| invalid("Invalid actor system name"))?;
+        validate_actor_path(path).map_err(|_| invalid("Invalid actor path"))?;
+
+        let addr = Self::new(system, host, ActorPath::new(path));
+
+        // a ':' left in the host is an invalid port, unless it's an IPv6 address
+        let ipv6 = addr.host.starts_with('[') && addr.host.ends_with(']');
+        if addr.host.is_empty()
+            || addr.host.contains(char::is_whitespace)
+            || (addr.host.contains(':') && !ipv6)
+        {
+            return Err(invalid("Invalid host or port"));
+        }
+
+        Ok(addr)
+    }
+}
+
+/// Error type when an actor address can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidAddress {
+    pub address: String,
+    pub cause: String,
+}
+
+impl std::error::Error for InvalidAddress {}
+
+impl fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid actor address \"{}\". Cause: {}",
+            self.address, self.cause
+        )
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// A phi accrual failure detector.
+///
+/// Instead of a boolean "alive" or "dead", the detector reports `phi`,
+/// the suspicion that a monitored node has failed. `phi` is derived from
+/// the time since the last heartbeat and the distribution of previous
+/// heartbeat intervals, so it adapts to the network conditions between
+/// the two nodes. A node is considered available while `phi` is below
+/// the threshold.
+///
+/// A `phi` of 1 means a 10% chance that the node is mistakenly considered
+/// failed, 2 a 1% chance, 3 a 0.1% chance and so on.
+///
+/// See "The φ Accrual Failure Detector" by Hayashibara et al.
+#[derive(Clone, Debug)]
+pub struct PhiAccrualFailureDetector {
+    threshold: f64,
+    max_sample_size: usize,
+    min_std_deviation: f64,
+    acceptable_pause: f64,
+    intervals: VecDeque<f64>,
+    last_heartbeat: Option<Instant>,
+}
+
+impl PhiAccrualFailureDetector {
+    /// Create a detector for heartbeats that are expected every
+    /// `first_heartbeat_estimate`, until actual intervals are observed.
+    #[must_use]
+    pub fn new(
+        threshold: f64,
+        max_sample_size: usize,
+        min_std_deviation: Duration,
+        acceptable_pause: Duration,
+        first_heartbeat_estimate: Duration,
+    ) -> Self {
+        let estimate = millis(first_heartbeat_estimate);
+        let deviation = estimate / 4.0;
+
+        // bootstrap the history with two intervals around the estimate
+        let mut intervals = VecDeque::with_capacity(max_sample_size);
+        intervals.push_back(estimate - deviation);
+        intervals.push_back(estimate + deviation);
+
+        Self {
+            threshold,
+            max_sample_size: max_sample_size.max(2),
+            min_std_deviation: millis(min_std_deviation),
+            acceptable_pause: millis(acceptable_pause),
+            intervals,
+            last_heartbeat: None,
+        }
+    }
+
+    /// Record a heartbeat received at `now`
+    pub fn heartbeat(&mut self, now: Instant) {
+        if let Some(last) = self.last_heartbeat {
+            if self.intervals.len() >= self.max_sample_size {
+                self.intervals.pop_front();
+            }
+            self.intervals
+                .push_back(millis(now.saturating_duration_since(last)));
+        }
+
+        self.last_heartbeat = Some(now);
+    }
+
+    /// Returns the suspicion level at `now`.
+    ///
+    /// `phi` is 0 until the first heartbeat is received.
+    #[must_use]
+    pub fn phi(&self, now: Instant) -> f64 {
+        let last = match self.last_heartbeat {
+            Some(last) => last,
+            None => return 0.0,
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let samples = self.intervals.len() as f64;
+        let mean = self.intervals.iter().sum::<f64>() / samples;
+        let variance = self
+            .intervals
+            .iter()
+            .map(|i| (i - mean).powi(2))
+            .sum::<f64>()
+            / samples;
+
+        phi(
+            millis(now.saturating_duration_since(last)),
+            mean + self.acceptable_pause,
+            variance.sqrt().max(self.min_std_deviation),
+        )
+    }
+
+    /// Returns true if the suspicion level at `now` is below the threshold
+    #[must_use]
+    pub fn is_available(&self, now: Instant) -> bool {
+        self.phi(now) < self.threshold
+    }
+}
+
+/// `-log10` of the probability that a heartbeat arrives later than
+/// `elapsed`, using a logistic approximation of the normal distribution
+fn phi(elapsed: f64, mean: f64, std_deviation: f64) -> f64 {
+    let y = (elapsed - mean) / std_deviation;
+    let e = (-y * y.mul_add(0.070_566 * y, 1.597_6)).exp();
+
+    if elapsed > mean {
+        -(e / (1.0 + e)).log10()
+    } else {
+        -(1.0 - 1.0 / (1.0 + e)).log10()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
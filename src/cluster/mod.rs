@@ -0,0 +1,735 @@
+pub(crate) mod failure_detector;
+pub(crate) mod pubsub;
+pub(crate) mod sharding;
+pub(crate) mod singleton;
+
+pub use self::failure_detector::PhiAccrualFailureDetector;
+pub use self::pubsub::DistributedPubSub;
+pub use self::sharding::{ClusterSharding, ShardRegionMsg, ShardRegionRef};
+pub use self::singleton::ClusterSingleton;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use config::Config;
+use serde::{Deserialize, Serialize};
+use slog::{info, warn};
+use uuid::Uuid;
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, Sender, Tell};
+use crate::{
+    actor::{
+        Actor, ActorAddress, ActorFactoryArgs, ActorPath, Channel, ChannelRef, Context,
+        CreateError, Publish, Topic,
+    },
+    remote::Remoting,
+    system::{SerializeError, Timer},
+};
+
+/// Cluster membership for actor systems connected by remoting.
+///
+/// A node joins the cluster by gossiping its membership state to one
+/// or more seed nodes. The seeds reply with the members they know of,
+/// after which nodes keep exchanging membership state with each other
+/// until every node has the same view of the cluster.
+///
+/// Each node sends heartbeats to the other members and monitors the
+/// heartbeats it receives using a `PhiAccrualFailureDetector`. A member
+/// whose heartbeats stop is marked unreachable and, if it stays
+/// unreachable, is removed from the cluster.
+///
+/// Changes in membership are published as `ClusterEvent`s on the
+/// channel returned by `events`:
+///
+/// ```ignore
+/// let remote = Remoting::start(&sys, "127.0.0.1:7001")?;
+/// let cluster = Cluster::join(&remote, &["127.0.0.1:7000".parse()?])?;
+///
+/// cluster.events().tell(Subscribe { actor: Box::new(listener), topic: All.into() }, None);
+/// ```
+///
+/// A node is identified by its remoting address and an incarnation uid
+/// that is new each time the node joins. A node restarted on the same
+/// address joins as a new incarnation that replaces the previous one.
+/// All nodes of a cluster must use the same actor system name.
+///
+/// A node that learns that the other members removed it, e.g. because
+/// it was unreachable for too long, leaves the cluster: `MemberRemoved`
+/// is published for the node and `is_up` returns false, so that the
+/// application can shut the node down or restart it.
+#[derive(Clone)]
+pub struct Cluster {
+    remoting: Remoting,
+    daemon: ActorRef<ClusterMsg>,
+    events: ChannelRef<ClusterEvent>,
+    members: Arc<RwLock<Vec<Member>>>,
+    up: Arc<AtomicBool>,
+    addr: SocketAddr,
+}
+
+impl Cluster {
+    /// Join the cluster using the seed nodes configured in
+    /// `cluster.seed_nodes`.
+    ///
+    /// A node with no seed nodes, or that is its own only seed node,
+    /// starts a new cluster.
+    pub fn start(remoting: &Remoting) -> Result<Self, ClusterError> {
+        let settings = ClusterSettings::from(&remoting.system().config());
+
+        let seeds = settings
+            .seed_nodes
+            .iter()
+            .map(|seed| {
+                seed.to_socket_addrs()
+                    .ok()
+                    .and_then(|mut addrs| addrs.next())
+                    .ok_or_else(|| ClusterError::InvalidSeed(seed.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::join(remoting, &seeds)
+    }
+
+    /// Join the cluster that the seed nodes are members of
+    pub fn join(remoting: &Remoting, seeds: &[SocketAddr]) -> Result<Self, ClusterError> {
+        let sys = remoting.system();
+
+        sys.register_internal::<ClusterMsg>(CLUSTER_MANIFEST)?;
+
+        let addr = remoting.local_addr();
+        let seeds: Vec<_> = seeds.iter().copied().filter(|seed| *seed != addr).collect();
+
+        let joined = Record {
+            addr,
+            uid: rand::random(),
+            status: MemberStatus::Up,
+            version: 1,
+            joined: SystemTime::now(),
+        };
+
+        let events = sys.sys_actor_of::<Channel<ClusterEvent>>("cluster_events")?;
+        let members = Arc::new(RwLock::new(vec![joined.member(false)]));
+        // a node without seed nodes starts a new cluster
+        let up = Arc::new(AtomicBool::new(seeds.is_empty()));
+
+        let args = DaemonArgs {
+            remoting: remoting.clone(),
+            joined,
+            seeds,
+            settings: ClusterSettings::from(&sys.config()),
+            events: events.clone(),
+            members: members.clone(),
+            up: up.clone(),
+        };
+        let daemon = sys.sys_actor_of_args::<ClusterDaemon, _>("cluster", args)?;
+
+        Ok(Self {
+            remoting: remoting.clone(),
+            daemon,
+            events,
+            members,
+            up,
+            addr,
+        })
+    }
+
+    /// Returns the address of this node
+    #[must_use]
+    pub const fn self_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns true once this node has joined the cluster, i.e. it has
+    /// received the membership state from another member or it started
+    /// a new cluster, and until it leaves or is removed
+    #[must_use]
+    pub fn is_up(&self) -> bool {
+        self.up.load(Ordering::Relaxed)
+    }
+
+    /// Returns the members of the cluster, including this node,
+    /// as currently known to this node
+    #[must_use]
+    pub fn members(&self) -> Vec<Member> {
+        self.members.read().unwrap().clone()
+    }
+
+    /// Returns the remoting the cluster communicates with
+    #[must_use]
+    pub const fn remoting(&self) -> &Remoting {
+        &self.remoting
+    }
+
+    /// Returns the channel that membership changes are published on
+    #[must_use]
+    pub const fn events(&self) -> &ChannelRef<ClusterEvent> {
+        &self.events
+    }
+
+    /// Leave the cluster.
+    ///
+    /// The other members are told that this node is removed and the node
+    /// stops taking part in gossip and heartbeats. `MemberRemoved` is
+    /// published for this node.
+    pub fn leave(&self) {
+        self.daemon.tell(ClusterMsg::Leave, None);
+    }
+}
+
+/// A member of the cluster
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Member {
+    pub addr: SocketAddr,
+
+    /// Identifies the incarnation of the node at `addr`
+    pub uid: u64,
+    pub status: MemberStatus,
+
+    /// The time the member joined the cluster, according to its own clock
+    pub joined: SystemTime,
+}
+
+impl Member {
+    /// Returns true if the member joined the cluster before `other`.
+    ///
+    /// Members that joined at the same time are ordered by address.
+    #[must_use]
+    pub fn is_older_than(&self, other: &Self) -> bool {
+        (self.joined, self.addr) < (other.joined, other.addr)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemberStatus {
+    /// The member is part of the cluster
+    Up,
+
+    /// The member's heartbeats are no longer received by this node
+    Unreachable,
+
+    /// The member has left or has been removed from the cluster
+    Removed,
+}
+
+/// Membership events published on `Cluster::events`
+#[derive(Clone, Debug)]
+pub enum ClusterEvent {
+    /// A member joined the cluster
+    MemberUp(Member),
+
+    /// A member's heartbeats are no longer received
+    MemberUnreachable(Member),
+
+    /// An unreachable member's heartbeats are received again
+    MemberReachable(Member),
+
+    /// A member left or was removed from the cluster
+    MemberRemoved(Member),
+}
+
+impl<'a> From<&'a ClusterEvent> for Topic {
+    fn from(evt: &ClusterEvent) -> Self {
+        match *evt {
+            ClusterEvent::MemberUp(_) => Self::from("member.up"),
+            ClusterEvent::MemberUnreachable(_) => Self::from("member.unreachable"),
+            ClusterEvent::MemberReachable(_) => Self::from("member.reachable"),
+            ClusterEvent::MemberRemoved(_) => Self::from("member.removed"),
+        }
+    }
+}
+
+/// Topics of the cluster events channel
+pub enum ClusterTopic {
+    MemberUp,
+    MemberUnreachable,
+    MemberReachable,
+    MemberRemoved,
+}
+
+impl From<ClusterTopic> for Topic {
+    fn from(topic: ClusterTopic) -> Self {
+        match topic {
+            ClusterTopic::MemberUp => Self::from("member.up"),
+            ClusterTopic::MemberUnreachable => Self::from("member.unreachable"),
+            ClusterTopic::MemberReachable => Self::from("member.reachable"),
+            ClusterTopic::MemberRemoved => Self::from("member.removed"),
+        }
+    }
+}
+
+const CLUSTER_MANIFEST: &str = "riker.cluster.ClusterMsg";
+const DAEMON_PATH: &str = "/system/cluster";
+
+/// The membership state that is gossiped between nodes.
+///
+/// Only the node itself changes the version of its record, except when
+/// an unreachable node is removed. A removed record is only replaced by
+/// a new incarnation of the node, i.e. a record with another uid that
+/// joined later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Record {
+    addr: SocketAddr,
+    uid: u64,
+    status: MemberStatus,
+    version: u64,
+    joined: SystemTime,
+}
+
+impl Record {
+    const fn member(&self, unreachable: bool) -> Member {
+        let status = match self.status {
+            MemberStatus::Up if unreachable => MemberStatus::Unreachable,
+            status => status,
+        };
+
+        Member {
+            addr: self.addr,
+            uid: self.uid,
+            status,
+            joined: self.joined,
+        }
+    }
+
+    fn supersedes(&self, other: Option<&Self>) -> bool {
+        match other {
+            None => true,
+            Some(other) if other.uid != self.uid => {
+                (self.joined, self.uid) > (other.joined, other.uid)
+            }
+            Some(other) if other.status == MemberStatus::Removed => false,
+            Some(_) if self.status == MemberStatus::Removed => true,
+            Some(other) => self.version > other.version,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) enum ClusterMsg {
+    /// The membership state known to the sending node
+    Gossip(SocketAddr, Vec<Record>),
+
+    /// A heartbeat from the node at the address
+    Heartbeat(SocketAddr),
+
+    HeartbeatTick,
+    GossipTick,
+    Leave,
+}
+
+#[derive(Clone)]
+struct DaemonArgs {
+    remoting: Remoting,
+    joined: Record,
+    seeds: Vec<SocketAddr>,
+    settings: ClusterSettings,
+    events: ChannelRef<ClusterEvent>,
+    members: Arc<RwLock<Vec<Member>>>,
+    up: Arc<AtomicBool>,
+}
+
+/// Gossips membership state, sends heartbeats and detects
+/// unreachable members
+struct ClusterDaemon {
+    remoting: Remoting,
+    addr: SocketAddr,
+    uid: u64,
+    seeds: Vec<SocketAddr>,
+    events: ChannelRef<ClusterEvent>,
+    members: Arc<RwLock<Vec<Member>>>,
+    up: Arc<AtomicBool>,
+    settings: ClusterSettings,
+    records: HashMap<SocketAddr, Record>,
+    detectors: HashMap<SocketAddr, PhiAccrualFailureDetector>,
+    unreachable: HashMap<SocketAddr, Instant>,
+    peers: HashMap<SocketAddr, ActorRef<ClusterMsg>>,
+    gossip_round: usize,
+    schedules: Vec<Uuid>,
+}
+
+impl ActorFactoryArgs<DaemonArgs> for ClusterDaemon {
+    fn create_args(args: DaemonArgs) -> Self {
+        let mut records = HashMap::new();
+        records.insert(args.joined.addr, args.joined);
+
+        Self {
+            remoting: args.remoting,
+            addr: args.joined.addr,
+            uid: args.joined.uid,
+            seeds: args.seeds,
+            events: args.events,
+            members: args.members,
+            up: args.up,
+            settings: args.settings,
+            records,
+            detectors: HashMap::new(),
+            unreachable: HashMap::new(),
+            peers: HashMap::new(),
+            gossip_round: 0,
+            schedules: Vec::new(),
+        }
+    }
+}
+
+impl ClusterDaemon {
+    fn gossip(&self) -> Vec<Record> {
+        self.records.values().copied().collect()
+    }
+
+    fn peer(
+        &mut self,
+        ctx: &Context<ClusterMsg>,
+        addr: SocketAddr,
+    ) -> Option<ActorRef<ClusterMsg>> {
+        if let Some(peer) = self.peers.get(&addr) {
+            return Some(peer.clone());
+        }
+
+        let address = ActorAddress::new(
+            &ctx.system.name(),
+            &addr.to_string(),
+            ActorPath::new(DAEMON_PATH),
+        );
+
+        match self.remoting.actor_ref::<ClusterMsg>(&address) {
+            Ok(peer) => {
+                self.peers.insert(addr, peer.clone());
+                Some(peer)
+            }
+            Err(e) => {
+                warn!(
+                    ctx.system.log(),
+                    "Failed to reach cluster node {}: {}", addr, e
+                );
+                None
+            }
+        }
+    }
+
+    fn send(&mut self, ctx: &Context<ClusterMsg>, addr: SocketAddr, msg: ClusterMsg) {
+        if let Some(peer) = self.peer(ctx, addr) {
+            peer.tell(msg, None);
+        }
+    }
+
+    /// Members, other than this node, that haven't been removed
+    fn others(&self) -> Vec<SocketAddr> {
+        let mut others: Vec<_> = self
+            .records
+            .values()
+            .filter(|r| r.addr != self.addr && r.status != MemberStatus::Removed)
+            .map(|r| r.addr)
+            .collect();
+        others.sort();
+        others
+    }
+
+    fn merge(&mut self, ctx: &Context<ClusterMsg>, from: SocketAddr, records: &[Record]) {
+        // a removed node that is still running is told that it's removed,
+        // but its view of the cluster isn't merged
+        let sender_removed = records
+            .iter()
+            .find(|theirs| theirs.addr == from && theirs.status != MemberStatus::Removed)
+            .and_then(|theirs| {
+                self.records
+                    .get(&from)
+                    .filter(|ours| ours.uid == theirs.uid)
+            })
+            .is_some_and(|ours| ours.status == MemberStatus::Removed);
+
+        if sender_removed {
+            let gossip = ClusterMsg::Gossip(self.addr, self.gossip());
+            self.send(ctx, from, gossip);
+            return;
+        }
+
+        for record in records {
+            // only this node changes its own record, but it learns
+            // when the other members have removed it
+            if record.addr == self.addr {
+                if record.uid == self.uid && record.status == MemberStatus::Removed {
+                    warn!(ctx.system.log(), "This node was removed from the cluster");
+                    self.leave(ctx);
+                    return;
+                }
+                continue;
+            }
+
+            if !record.supersedes(self.records.get(&record.addr)) {
+                continue;
+            }
+
+            let previous = self.records.insert(record.addr, *record);
+            let new_incarnation = !matches!(previous, Some(previous) if previous.uid == record.uid);
+
+            // a member is removed, or a new incarnation replaces it
+            if let Some(previous) = previous.filter(|previous| {
+                previous.status != MemberStatus::Removed
+                    && (new_incarnation || record.status == MemberStatus::Removed)
+            }) {
+                self.member_removed(ctx, previous);
+            }
+
+            if record.status == MemberStatus::Up && new_incarnation {
+                self.member_up(ctx, record.addr);
+            }
+        }
+
+        self.up.store(true, Ordering::Relaxed);
+
+        // the sender is missing records that this node has
+        let behind = self
+            .records
+            .values()
+            .any(|ours| ours.supersedes(records.iter().find(|theirs| theirs.addr == ours.addr)));
+
+        if behind {
+            let gossip = ClusterMsg::Gossip(self.addr, self.gossip());
+            self.send(ctx, from, gossip);
+        }
+    }
+
+    fn member_up(&mut self, ctx: &Context<ClusterMsg>, addr: SocketAddr) {
+        let settings = &self.settings;
+        let mut detector = PhiAccrualFailureDetector::new(
+            settings.phi_threshold,
+            settings.max_sample_size,
+            settings.min_std_deviation,
+            settings.acceptable_heartbeat_pause,
+            settings.heartbeat_interval,
+        );
+        detector.heartbeat(Instant::now());
+        self.detectors.insert(addr, detector);
+
+        info!(ctx.system.log(), "Cluster member {} is up", addr);
+        self.publish(ClusterEvent::MemberUp(self.member(addr)));
+    }
+
+    fn member_removed(&mut self, ctx: &Context<ClusterMsg>, record: Record) {
+        let addr = record.addr;
+        self.detectors.remove(&addr);
+        self.unreachable.remove(&addr);
+        self.peers.remove(&addr);
+
+        let removed = Record {
+            status: MemberStatus::Removed,
+            ..record
+        };
+
+        info!(ctx.system.log(), "Cluster member {} is removed", addr);
+        self.publish(ClusterEvent::MemberRemoved(removed.member(false)));
+    }
+
+    fn heartbeat(&mut self, ctx: &Context<ClusterMsg>) {
+        for addr in self.others() {
+            self.send(ctx, addr, ClusterMsg::Heartbeat(self.addr));
+        }
+
+        let now = Instant::now();
+        let remove_after = self.settings.remove_unreachable_after;
+
+        for addr in self.others() {
+            let available = self.detectors[&addr].is_available(now);
+
+            match self.unreachable.get(&addr).copied() {
+                None if !available => {
+                    warn!(ctx.system.log(), "Cluster member {} is unreachable", addr);
+                    self.unreachable.insert(addr, now);
+                    self.publish(ClusterEvent::MemberUnreachable(self.member(addr)));
+                }
+                Some(_) if available => {
+                    info!(ctx.system.log(), "Cluster member {} is reachable", addr);
+                    self.unreachable.remove(&addr);
+                    self.publish(ClusterEvent::MemberReachable(self.member(addr)));
+                }
+                Some(since) if now.duration_since(since) >= remove_after => {
+                    let record = self.records.get_mut(&addr).unwrap();
+                    record.status = MemberStatus::Removed;
+                    record.version += 1;
+
+                    let record = *record;
+                    self.member_removed(ctx, record);
+                    self.gossip_all(ctx);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Gossip with the next reachable member, or with the seed nodes
+    /// while no other member is known
+    fn gossip_round(&mut self, ctx: &Context<ClusterMsg>) {
+        let reachable: Vec<_> = self
+            .others()
+            .into_iter()
+            .filter(|addr| !self.unreachable.contains_key(addr))
+            .collect();
+
+        let targets = if reachable.is_empty() {
+            self.seeds.clone()
+        } else {
+            self.gossip_round = self.gossip_round.wrapping_add(1);
+            vec![reachable[self.gossip_round % reachable.len()]]
+        };
+
+        for addr in targets {
+            self.send(ctx, addr, ClusterMsg::Gossip(self.addr, self.gossip()));
+        }
+    }
+
+    fn gossip_all(&mut self, ctx: &Context<ClusterMsg>) {
+        for addr in self.others() {
+            self.send(ctx, addr, ClusterMsg::Gossip(self.addr, self.gossip()));
+        }
+    }
+
+    fn leave(&mut self, ctx: &Context<ClusterMsg>) {
+        for id in self.schedules.drain(..) {
+            ctx.cancel_schedule(id);
+        }
+
+        let record = self.records.get_mut(&self.addr).unwrap();
+        record.status = MemberStatus::Removed;
+        record.version += 1;
+
+        info!(ctx.system.log(), "Leaving the cluster");
+        self.gossip_all(ctx);
+        self.up.store(false, Ordering::Relaxed);
+        self.publish(ClusterEvent::MemberRemoved(self.member(self.addr)));
+        ctx.stop(&ctx.myself);
+    }
+
+    fn member(&self, addr: SocketAddr) -> Member {
+        self.records[&addr].member(self.unreachable.contains_key(&addr))
+    }
+
+    fn publish(&self, evt: ClusterEvent) {
+        let mut members: Vec<_> = self
+            .records
+            .values()
+            .filter(|r| r.status != MemberStatus::Removed)
+            .map(|r| r.member(self.unreachable.contains_key(&r.addr)))
+            .collect();
+        members.sort_by_key(|m| m.addr);
+        *self.members.write().unwrap() = members;
+
+        let topic = Topic::from(&evt);
+        self.events.tell(Publish { topic, msg: evt }, None);
+    }
+}
+
+impl Actor for ClusterDaemon {
+    type Msg = ClusterMsg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.schedules = vec![
+            ctx.schedule(
+                self.settings.heartbeat_interval,
+                self.settings.heartbeat_interval,
+                ctx.myself(),
+                None,
+                ClusterMsg::HeartbeatTick,
+            ),
+            ctx.schedule(
+                Duration::from_millis(0),
+                self.settings.gossip_interval,
+                ctx.myself(),
+                None,
+                ClusterMsg::GossipTick,
+            ),
+        ];
+    }
+
+    fn post_stop(&mut self) {
+        self.peers.clear();
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            ClusterMsg::Gossip(from, records) => self.merge(ctx, from, &records),
+            ClusterMsg::Heartbeat(from) => {
+                if let Some(detector) = self.detectors.get_mut(&from) {
+                    detector.heartbeat(Instant::now());
+                }
+            }
+            ClusterMsg::HeartbeatTick => self.heartbeat(ctx),
+            ClusterMsg::GossipTick => self.gossip_round(ctx),
+            ClusterMsg::Leave => self.leave(ctx),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ClusterSettings {
+    pub seed_nodes: Vec<String>,
+    pub heartbeat_interval: Duration,
+    pub gossip_interval: Duration,
+    pub phi_threshold: f64,
+    pub max_sample_size: usize,
+    pub min_std_deviation: Duration,
+    pub acceptable_heartbeat_pause: Duration,
+    pub remove_unreachable_after: Duration,
+}
+
+impl<'a> From<&'a Config> for ClusterSettings {
+    fn from(config: &Config) -> Self {
+        let millis = |key: &str| Duration::from_millis(config.get::<u64>(key).unwrap());
+
+        Self {
+            seed_nodes: config.get::<Vec<String>>("cluster.seed_nodes").unwrap(),
+            heartbeat_interval: millis("cluster.heartbeat_interval_millis"),
+            gossip_interval: millis("cluster.gossip_interval_millis"),
+            phi_threshold: config.get::<f64>("cluster.phi_threshold").unwrap(),
+            max_sample_size: config.get::<usize>("cluster.max_sample_size").unwrap(),
+            min_std_deviation: millis("cluster.min_std_deviation_millis"),
+            acceptable_heartbeat_pause: millis("cluster.acceptable_heartbeat_pause_millis"),
+            remove_unreachable_after: millis("cluster.remove_unreachable_after_millis"),
+        }
+    }
+}
+
+/// Error type when joining a cluster fails.
+#[derive(Debug)]
+pub enum ClusterError {
+    InvalidSeed(String),
+    Serialize(SerializeError),
+    Create(CreateError),
+}
+
+impl std::error::Error for ClusterError {}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidSeed(ref seed) => f.write_str(&format!(
+                "Failed to join cluster. Cause: Invalid seed node address ({})",
+                seed
+            )),
+            Self::Serialize(ref err) => {
+                f.write_str(&format!("Failed to join cluster. Cause: {}", err))
+            }
+            Self::Create(ref err) => {
+                f.write_str(&format!("Failed to join cluster. Cause: {}", err))
+            }
+        }
+    }
+}
+
+impl From<SerializeError> for ClusterError {
+    fn from(err: SerializeError) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+impl From<CreateError> for ClusterError {
+    fn from(err: CreateError) -> Self {
+        Self::Create(err)
+    }
+}
@@ -0,0 +1,598 @@
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    net::SocketAddr,
+    time::Duration,
+};
+
+use config::Config;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{debug, warn};
+use uuid::Uuid;
+
+use crate::actor_ref::{
+    ActorRef, ActorRefFactory, ActorReference, BasicActorRef, BoxedTell, Sender, Tell,
+};
+use crate::{
+    actor::{
+        channel::Subscriber, Actor, ActorAddress, ActorFactoryArgs, ActorPath, All, ChannelMsg,
+        ChannelRef, Context, CreateError, Filter, Props, Subscribe, SysTopic, Topic,
+    },
+    cluster::{Cluster, ClusterError, ClusterEvent},
+    system::{SerializedMessage, SystemEvent, SystemMsg, Timer},
+    Message,
+};
+
+/// Publish-subscribe across the members of a cluster.
+///
+/// A distributed channel is used like a `Channel`, but the actors that
+/// subscribe to it on any node receive the messages published on every
+/// node:
+///
+/// ```ignore
+/// let pubsub = DistributedPubSub::new(&cluster)?;
+/// let orders = pubsub.channel::<Order>("orders")?;
+///
+/// orders.tell(Subscribe { actor: Box::new(auditor), topic: "created".into() }, None);
+/// orders.tell(Publish { msg: order, topic: "created".into() }, None);
+/// ```
+///
+/// Each node replicates the topics its actors subscribe to to the other
+/// members, so that a message is only sent to the nodes that have
+/// subscribers for its topic.
+///
+/// Actors subscribed with `SubscribeGroup` form a group across the
+/// cluster. A message published to the topic is sent to one member of
+/// each group, in turn, rather than to every member.
+///
+/// The channel's message type must be registered with the serialization
+/// registry on every node. The sender of a message published on another
+/// node is not sent.
+#[derive(Clone)]
+pub struct DistributedPubSub {
+    cluster: Cluster,
+    guardian: BasicActorRef,
+}
+
+impl DistributedPubSub {
+    pub fn new(cluster: &Cluster) -> Result<Self, ClusterError> {
+        let sys = cluster.remoting().system();
+        sys.register_internal::<PubSubEnvelope>(PUBSUB_ENVELOPE_MANIFEST)?;
+
+        let guardian = sys.sys_actor_of::<PubSubGuardian>("pubsub")?;
+
+        Ok(Self {
+            cluster: cluster.clone(),
+            guardian: guardian.into(),
+        })
+    }
+
+    /// Start the distributed channel named `name` on this node and return
+    /// a reference to it.
+    ///
+    /// The channel is created at `/system/pubsub/<name>`. Channels with the
+    /// same name on different nodes are the same distributed channel.
+    pub fn channel<Msg>(&self, name: &str) -> Result<ChannelRef<Msg>, CreateError>
+    where
+        Msg: Message + Serialize + DeserializeOwned,
+    {
+        let sys = self.cluster.remoting().system();
+        let props = Props::new_args(
+            Mediator::<Msg>::create_args,
+            (
+                self.cluster.clone(),
+                name.to_string(),
+                PubSubSettings::from(&sys.config()),
+            ),
+        );
+        let mediator = sys
+            .provider
+            .create_actor(props, name, &self.guardian, sys)?;
+
+        let props = Props::new_args(PubSubProxy::create_args, mediator.clone());
+        sys.provider
+            .create_actor(props, "channel", &mediator.into(), sys)
+    }
+}
+
+/// The parent of the distributed channels on a node
+#[derive(Default)]
+struct PubSubGuardian;
+
+impl Actor for PubSubGuardian {
+    type Msg = ();
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {}
+}
+
+const PUBSUB_ENVELOPE_MANIFEST: &str = "riker.cluster.PubSubEnvelope";
+
+/// A topic subscribed to on a node, by a group or by individual actors
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Registration {
+    topic: String,
+    group: Option<String>,
+}
+
+impl Registration {
+    fn new(topic: &Topic, group: Option<String>) -> Self {
+        Self {
+            topic: topic.to_string(),
+            group,
+        }
+    }
+
+    /// Whether a message published to `topic` is for this registration
+    fn matches(&self, topic: &Topic) -> bool {
+        Topic::from(self.topic.as_str()).matches(topic)
+    }
+}
+
+/// A message sent to the distributed channel of another node
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum PubSubEnvelope {
+    /// The topics subscribed to on the sending node
+    Registry(SocketAddr, Vec<Registration>),
+
+    /// A message for the subscribers of a topic. For a group, `topic`
+    /// is the topic the group subscribed to.
+    Publish {
+        topic: String,
+        group: Option<String>,
+        msg: SerializedMessage,
+    },
+}
+
+#[derive(Clone, Debug)]
+enum PubSubMsg<Msg: Message> {
+    Channel(ChannelMsg<Msg>),
+
+    /// A message published on another node
+    Deliver {
+        topic: Topic,
+        group: Option<String>,
+        msg: Msg,
+    },
+
+    Registry(SocketAddr, Vec<Registration>),
+    GossipTick,
+    Member(ClusterEvent),
+}
+
+impl<Msg: Message> From<ClusterEvent> for PubSubMsg<Msg> {
+    fn from(evt: ClusterEvent) -> Self {
+        Self::Member(evt)
+    }
+}
+
+/// The actors subscribed to a registration on this node
+struct Subscribers<Msg: Message> {
+    actors: Vec<Subscriber<Msg>>,
+    next: usize,
+}
+
+/// Keeps the subscriptions of this node, replicates them to the other
+/// members and routes published messages to the nodes with subscribers
+struct Mediator<Msg: Message> {
+    cluster: Cluster,
+    name: String,
+    settings: PubSubSettings,
+    subs: HashMap<Registration, Subscribers<Msg>>,
+    registries: HashMap<SocketAddr, HashSet<Registration>>,
+    turns: HashMap<Registration, usize>,
+    remotes: HashMap<SocketAddr, ActorRef<PubSubEnvelope>>,
+    gossip: Option<Uuid>,
+}
+
+type MediatorArgs = (Cluster, String, PubSubSettings);
+
+impl<Msg> ActorFactoryArgs<MediatorArgs> for Mediator<Msg>
+where
+    Msg: Message + Serialize + DeserializeOwned,
+{
+    fn create_args((cluster, name, settings): MediatorArgs) -> Self {
+        Self {
+            cluster,
+            name,
+            settings,
+            subs: HashMap::new(),
+            registries: HashMap::new(),
+            turns: HashMap::new(),
+            remotes: HashMap::new(),
+            gossip: None,
+        }
+    }
+}
+
+impl<Msg> Mediator<Msg>
+where
+    Msg: Message + Serialize + DeserializeOwned,
+{
+    fn receive_channel(
+        &mut self,
+        ctx: &Context<PubSubMsg<Msg>>,
+        msg: ChannelMsg<Msg>,
+        sender: &Sender,
+    ) {
+        let before: HashSet<Registration> = self.subs.keys().cloned().collect();
+
+        match msg {
+            ChannelMsg::Publish(p) => {
+                self.publish(ctx, &p.topic, &p.msg, sender);
+                return;
+            }
+            ChannelMsg::Subscribe(sub) => {
+                self.subscribe(Registration::new(&sub.topic, None), sub.actor, None);
+            }
+            ChannelMsg::SubscribeGroup(sub) => {
+                let registration = Registration::new(&sub.topic, Some(sub.group));
+                self.subscribe(registration, sub.actor, None);
+            }
+            ChannelMsg::SubscribeFiltered(sub) => {
+                let registration = Registration::new(&sub.topic, None);
+                self.subscribe(registration, sub.actor, Some(sub.filter));
+            }
+            ChannelMsg::Unsubscribe(unsub) => {
+                let topic = unsub.topic.to_string();
+                self.unsubscribe(|r| r.topic == topic, |a| a == &unsub.actor);
+            }
+            ChannelMsg::UnsubscribeAll(unsub) => self.unsubscribe(|_| true, |a| a == &unsub.actor),
+        }
+
+        // the other members only need to know about new or removed topics
+        if self.subs.keys().cloned().collect::<HashSet<_>>() != before {
+            self.gossip(ctx, None);
+        }
+    }
+
+    fn subscribe(
+        &mut self,
+        registration: Registration,
+        actor: BoxedTell<Msg>,
+        filter: Option<Filter<Msg>>,
+    ) {
+        self.subs
+            .entry(registration)
+            .or_insert_with(|| Subscribers {
+                actors: Vec::new(),
+                next: 0,
+            })
+            .actors
+            .push(Subscriber { actor, filter });
+    }
+
+    fn unsubscribe(
+        &mut self,
+        registration: impl Fn(&Registration) -> bool,
+        actor: impl Fn(&BoxedTell<Msg>) -> bool,
+    ) {
+        for (r, subs) in &mut self.subs {
+            if registration(r) {
+                subs.actors.retain(|a| !actor(&a.actor));
+            }
+        }
+        self.subs.retain(|_, subs| !subs.actors.is_empty());
+    }
+
+    fn registrations(&self) -> Vec<Registration> {
+        self.subs.keys().cloned().collect()
+    }
+
+    /// Send the registrations of this node to `member`, or to every member
+    fn gossip(&mut self, ctx: &Context<PubSubMsg<Msg>>, member: Option<SocketAddr>) {
+        let self_addr = self.cluster.self_addr();
+        let members: Vec<SocketAddr> = match member {
+            Some(member) => vec![member],
+            None => self.cluster.members().into_iter().map(|m| m.addr).collect(),
+        };
+
+        let registry = PubSubEnvelope::Registry(self_addr, self.registrations());
+        for addr in members.into_iter().filter(|addr| *addr != self_addr) {
+            self.send(ctx, addr, registry.clone());
+        }
+    }
+
+    fn publish(
+        &mut self,
+        ctx: &Context<PubSubMsg<Msg>>,
+        topic: &Topic,
+        msg: &Msg,
+        sender: &Sender,
+    ) {
+        self.deliver(topic, None, msg, sender);
+
+        let self_addr = self.cluster.self_addr();
+        let members: HashSet<SocketAddr> =
+            self.cluster.members().into_iter().map(|m| m.addr).collect();
+
+        let serialized = match ctx.system.serialization().serialize(msg) {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!(
+                    ctx.system.log(),
+                    "Failed to publish message to {}: {}", self.name, e
+                );
+                return;
+            }
+        };
+
+        // the nodes with actors subscribed to the topic
+        let nodes: Vec<SocketAddr> = self
+            .registries
+            .iter()
+            .filter(|(addr, registry)| {
+                members.contains(addr)
+                    && registry
+                        .iter()
+                        .any(|r| r.group.is_none() && r.matches(topic))
+            })
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in nodes {
+            let envelope = PubSubEnvelope::Publish {
+                topic: topic.to_string(),
+                group: None,
+                msg: serialized.clone(),
+            };
+            self.send(ctx, addr, envelope);
+        }
+
+        // the nodes with members of each group subscribed to the topic
+        let mut groups: HashMap<Registration, Vec<SocketAddr>> = HashMap::new();
+        let local = self.subs.keys().map(|r| (self_addr, r));
+        let remote = self
+            .registries
+            .iter()
+            .filter(|(addr, _)| members.contains(addr))
+            .flat_map(|(addr, registry)| registry.iter().map(move |r| (*addr, r)));
+
+        for (addr, registration) in local.chain(remote) {
+            if registration.group.is_some() && registration.matches(topic) {
+                groups.entry(registration.clone()).or_default().push(addr);
+            }
+        }
+
+        for (registration, mut nodes) in groups {
+            nodes.sort();
+            let turn = self.turns.entry(registration.clone()).or_default();
+            let addr = nodes[*turn % nodes.len()];
+            *turn = turn.wrapping_add(1);
+
+            if addr == self_addr {
+                let topic = Topic::from(registration.topic);
+                self.deliver(&topic, registration.group, msg, sender);
+            } else {
+                let envelope = PubSubEnvelope::Publish {
+                    topic: registration.topic,
+                    group: registration.group,
+                    msg: serialized.clone(),
+                };
+                self.send(ctx, addr, envelope);
+            }
+        }
+    }
+
+    /// Deliver to the actors subscribed to the topic on this node, or
+    /// to the next member of the group
+    fn deliver(&mut self, topic: &Topic, group: Option<String>, msg: &Msg, sender: &Sender) {
+        if let Some(group) = group {
+            let registration = Registration::new(topic, Some(group));
+            if let Some(subs) = self.subs.get_mut(&registration) {
+                let next = subs.next % subs.actors.len();
+                subs.next = next + 1;
+                subs.actors[next].actor.tell(msg.clone(), sender.clone());
+            }
+            return;
+        }
+
+        let subs = self
+            .subs
+            .iter()
+            .filter(|(r, _)| r.group.is_none() && r.matches(topic));
+
+        // a subscriber with several matching subscriptions is sent the message once
+        let mut seen = HashSet::new();
+        for (_, subs) in subs {
+            for sub in subs.actors.iter().filter(|sub| sub.accepts(msg)) {
+                if seen.insert((sub.actor.path(), sub.actor.conversion())) {
+                    sub.actor.tell(msg.clone(), sender.clone());
+                }
+            }
+        }
+    }
+
+    /// Send to the distributed channel of another node
+    fn send(&mut self, ctx: &Context<PubSubMsg<Msg>>, addr: SocketAddr, envelope: PubSubEnvelope) {
+        if let Some(remote) = self.remotes.get(&addr) {
+            remote.tell(envelope, None);
+            return;
+        }
+
+        let path = format!("/system/pubsub/{}/inbound", self.name);
+        let address =
+            ActorAddress::new(&ctx.system.name(), &addr.to_string(), ActorPath::new(&path));
+
+        match self
+            .cluster
+            .remoting()
+            .actor_ref::<PubSubEnvelope>(&address)
+        {
+            Ok(remote) => {
+                remote.tell(envelope, None);
+                self.remotes.insert(addr, remote);
+            }
+            Err(e) => warn!(
+                ctx.system.log(),
+                "Failed to reach channel {}: {}", address, e
+            ),
+        }
+    }
+
+    fn member_event(&mut self, ctx: &Context<PubSubMsg<Msg>>, evt: &ClusterEvent) {
+        debug!(ctx.system.log(), "Channel {} received {:?}", self.name, evt);
+
+        match evt {
+            ClusterEvent::MemberUp(m) => self.gossip(ctx, Some(m.addr)),
+            ClusterEvent::MemberRemoved(m) if m.addr == self.cluster.self_addr() => {
+                // this node left the cluster
+                if let Some(id) = self.gossip.take() {
+                    ctx.cancel_schedule(id);
+                }
+                self.registries.clear();
+            }
+            ClusterEvent::MemberRemoved(m) => {
+                self.registries.remove(&m.addr);
+                if let Some(remote) = self.remotes.remove(&m.addr) {
+                    ctx.stop(&remote);
+                }
+            }
+            ClusterEvent::MemberUnreachable(_) | ClusterEvent::MemberReachable(_) => {}
+        }
+    }
+}
+
+impl<Msg> Actor for Mediator<Msg>
+where
+    Msg: Message + Serialize + DeserializeOwned,
+{
+    type Msg = PubSubMsg<Msg>;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        if let Err(e) = ctx.actor_of_args::<PubSubInbound<Msg>, _>("inbound", ctx.myself()) {
+            warn!(ctx.system.log(), "Failed to start channel inbound: {}", e);
+        }
+
+        self.cluster.events().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+
+        // subscribers that terminate without unsubscribing are removed
+        // when their ActorTerminated event is received
+        ctx.system.sys_events().tell(
+            Subscribe {
+                actor: Box::new(BasicActorRef::from(ctx.myself())),
+                topic: SysTopic::ActorTerminated.into(),
+            },
+            None,
+        );
+
+        let interval = self.settings.gossip_interval;
+        self.gossip = Some(ctx.schedule(
+            interval,
+            interval,
+            ctx.myself(),
+            None,
+            PubSubMsg::GossipTick,
+        ));
+    }
+
+    fn post_stop(&mut self) {
+        self.remotes.clear();
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        match msg {
+            PubSubMsg::Channel(msg) => self.receive_channel(ctx, msg, &sender),
+            PubSubMsg::Deliver { topic, group, msg } => self.deliver(&topic, group, &msg, &None),
+            PubSubMsg::Registry(addr, registrations) => {
+                self.registries
+                    .insert(addr, registrations.into_iter().collect());
+            }
+            PubSubMsg::GossipTick => self.gossip(ctx, None),
+            PubSubMsg::Member(evt) => self.member_event(ctx, &evt),
+        }
+    }
+
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            let topics = self.subs.len();
+            let path = terminated.actor.path();
+            self.unsubscribe(|_| true, |a| a.path() == path);
+
+            // the other members only need to know about removed topics
+            if self.subs.len() != topics {
+                self.gossip(ctx, None);
+            }
+        }
+    }
+}
+
+/// The `ChannelRef` returned by `DistributedPubSub::channel`
+struct PubSubProxy<Msg: Message> {
+    mediator: ActorRef<PubSubMsg<Msg>>,
+}
+
+impl<Msg: Message> ActorFactoryArgs<ActorRef<PubSubMsg<Msg>>> for PubSubProxy<Msg> {
+    fn create_args(mediator: ActorRef<PubSubMsg<Msg>>) -> Self {
+        Self { mediator }
+    }
+}
+
+impl<Msg: Message> Actor for PubSubProxy<Msg> {
+    type Msg = ChannelMsg<Msg>;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.mediator.send_msg(PubSubMsg::Channel(msg), sender);
+    }
+}
+
+/// Receives the messages sent to the distributed channel by other nodes
+struct PubSubInbound<Msg: Message> {
+    mediator: ActorRef<PubSubMsg<Msg>>,
+    msg: PhantomData<Msg>,
+}
+
+impl<Msg: Message> ActorFactoryArgs<ActorRef<PubSubMsg<Msg>>> for PubSubInbound<Msg> {
+    fn create_args(mediator: ActorRef<PubSubMsg<Msg>>) -> Self {
+        Self {
+            mediator,
+            msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg: Message> Actor for PubSubInbound<Msg> {
+    type Msg = PubSubEnvelope;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, envelope: Self::Msg, _sender: Sender) {
+        match envelope {
+            PubSubEnvelope::Registry(addr, registrations) => {
+                self.mediator
+                    .tell(PubSubMsg::Registry(addr, registrations), None);
+            }
+            PubSubEnvelope::Publish { topic, group, msg } => {
+                match ctx.system.serialization().deserialize(&msg) {
+                    Ok(msg) => {
+                        let topic = Topic::from(topic);
+                        self.mediator
+                            .tell(PubSubMsg::Deliver { topic, group, msg }, None);
+                    }
+                    Err(e) => warn!(
+                        ctx.system.log(),
+                        "Failed to receive published message: {}", e
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PubSubSettings {
+    gossip_interval: Duration,
+}
+
+impl<'a> From<&'a Config> for PubSubSettings {
+    fn from(config: &Config) -> Self {
+        Self {
+            gossip_interval: Duration::from_millis(
+                config.get::<u64>("pubsub.gossip_interval_millis").unwrap(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,652 @@
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    net::SocketAddr,
+    time::Duration,
+};
+
+use config::Config;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{debug, warn};
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, ActorReference, BasicActorRef, Sender, Tell};
+use crate::{
+    actor::{
+        Actor, ActorAddress, ActorFactoryArgs, ActorPath, All, Context, CreateError, EntityManager,
+        EntityManagerRef, EntityMsg, Props, Subscribe,
+    },
+    cluster::{Cluster, ClusterError, ClusterEvent, MemberStatus},
+    system::{SerializedMessage, SystemEvent, SystemMsg, Timer},
+    Message,
+};
+
+pub type ShardRegionRef<Msg> = ActorRef<ShardRegionMsg<Msg>>;
+
+/// Cluster sharding distributes entity actors across the nodes of a
+/// cluster by entity id.
+///
+/// Each node starts a shard region for an entity type. Messages for
+/// entities are sent to the local region as `EntityMsg`s, which the
+/// region routes to the node that owns the entity:
+///
+/// ```ignore
+/// let sharding = ClusterSharding::new(&cluster)?;
+/// let accounts = sharding.start::<BankAccount>("accounts")?;
+///
+/// accounts.tell(EntityMsg::new("acc-1", Deposit(100)), None);
+/// ```
+///
+/// Entity ids are grouped into `sharding.number_of_shards` shards. Each
+/// shard is owned by one reachable member, chosen using rendezvous
+/// hashing so that every node agrees on the owner without coordination.
+/// A shard owned by this node is an `EntityManager` that creates the
+/// entity actors and passivates them when they are idle.
+///
+/// When membership changes shards are rebalanced: a region stops the
+/// shards that are now owned by another node. Before a region starts a
+/// shard, it asks the member that would own the shard without this node
+/// to hand it off, and buffers the shard's messages, up to
+/// `sharding.buffer_size`, until that member has stopped the shard or
+/// `sharding.hand_off_timeout_millis` has passed. The entities are
+/// created on their new node by the messages sent to them. Persistent
+/// entities recover their state there.
+///
+/// A region that receives a message for a shard it doesn't own routes
+/// it again, which happens while the nodes' views of the cluster differ.
+///
+/// The entity message type must be registered with the serialization
+/// registry on every node. The sender of a message routed to another
+/// node is not sent.
+#[derive(Clone)]
+pub struct ClusterSharding {
+    cluster: Cluster,
+    guardian: BasicActorRef,
+}
+
+impl ClusterSharding {
+    pub fn new(cluster: &Cluster) -> Result<Self, ClusterError> {
+        let sys = cluster.remoting().system();
+        sys.register_internal::<ShardEnvelope>(SHARD_ENVELOPE_MANIFEST)?;
+
+        let guardian = sys.sys_actor_of::<ShardingGuardian>("sharding")?;
+
+        Ok(Self {
+            cluster: cluster.clone(),
+            guardian: guardian.into(),
+        })
+    }
+
+    /// Start the shard region on this node for the entities
+    /// of an entity type.
+    ///
+    /// The region is created at `/system/sharding/<type_name>`. A region
+    /// with the same type name must be started on every node.
+    pub fn start<A>(&self, type_name: &str) -> Result<ShardRegionRef<A::Msg>, CreateError>
+    where
+        A: ActorFactoryArgs<String>,
+        A::Msg: Serialize + DeserializeOwned,
+    {
+        let sys = self.cluster.remoting().system();
+        let props = Props::new_args(
+            ShardRegion::<A>::create_args,
+            (self.cluster.clone(), type_name.to_string()),
+        );
+
+        sys.provider
+            .create_actor(props, type_name, &self.guardian, sys)
+    }
+}
+
+/// The parent of the shard regions on a node
+#[derive(Default)]
+struct ShardingGuardian;
+
+impl Actor for ShardingGuardian {
+    type Msg = ();
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {}
+}
+
+#[derive(Clone, Debug)]
+pub enum ShardRegionMsg<Msg: Message> {
+    /// Route a message to the entity's shard, wherever it is
+    Tell(EntityMsg<Msg>),
+
+    /// A message routed from another node, `hops` times so far.
+    /// It's routed again if this node doesn't own the entity's shard.
+    Deliver {
+        msg: EntityMsg<Msg>,
+        hops: u32,
+    },
+
+    /// Another node owns the shard and asks this node to stop it
+    HandOff(u32),
+
+    /// The previous owner of the shard has stopped it
+    HandOffDone(u32),
+
+    HandOffTimeout {
+        shard: u32,
+        attempt: u64,
+    },
+
+    /// A change in cluster membership
+    Member(ClusterEvent),
+}
+
+impl<Msg: Message> From<EntityMsg<Msg>> for ShardRegionMsg<Msg> {
+    fn from(msg: EntityMsg<Msg>) -> Self {
+        Self::Tell(msg)
+    }
+}
+
+impl<Msg: Message> From<ClusterEvent> for ShardRegionMsg<Msg> {
+    fn from(evt: ClusterEvent) -> Self {
+        Self::Member(evt)
+    }
+}
+
+const SHARD_ENVELOPE_MANIFEST: &str = "riker.cluster.ShardEnvelope";
+
+/// The number of times a message is routed between regions before it's
+/// dropped. Messages are routed more than once only while the regions'
+/// views of the cluster differ.
+const MAX_HOPS: u32 = 3;
+
+/// A message sent to the shard region of another node
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum ShardEnvelope {
+    /// An entity message routed to the region that owns its shard
+    Tell {
+        entity_id: String,
+        msg: SerializedMessage,
+        hops: u32,
+    },
+
+    HandOff(u32),
+    HandOffDone(u32),
+}
+
+type Buffer<Msg> = Vec<(EntityMsg<Msg>, Sender)>;
+
+enum Shard<Msg: Message> {
+    /// This node owns the shard and waits for the previous owner
+    /// to hand it off
+    TakingOver {
+        attempt: u64,
+        buffer: Buffer<Msg>,
+    },
+
+    Running(EntityManagerRef<Msg>),
+
+    /// The shard is stopping because another member owns it
+    HandingOff(Buffer<Msg>),
+}
+
+/// Routes entity messages to local shards or to the regions
+/// of the nodes that own them
+struct ShardRegion<A: Actor> {
+    cluster: Cluster,
+    type_name: String,
+    settings: ShardingSettings,
+    members: Vec<SocketAddr>,
+    shards: HashMap<u32, Shard<A::Msg>>,
+    attempts: u64,
+    /// Shards that other nodes asked for before this node knew
+    /// that it no longer owns them
+    hand_off_requests: HashSet<u32>,
+    regions: HashMap<SocketAddr, ActorRef<ShardEnvelope>>,
+}
+
+impl<A> ActorFactoryArgs<(Cluster, String)> for ShardRegion<A>
+where
+    A: ActorFactoryArgs<String>,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    fn create_args((cluster, type_name): (Cluster, String)) -> Self {
+        let settings = ShardingSettings::from(&cluster.remoting().system().config());
+
+        Self {
+            cluster,
+            type_name,
+            settings,
+            members: Vec::new(),
+            shards: HashMap::new(),
+            attempts: 0,
+            hand_off_requests: HashSet::new(),
+            regions: HashMap::new(),
+        }
+    }
+}
+
+impl<A> ShardRegion<A>
+where
+    A: ActorFactoryArgs<String>,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    fn shard_id(&self, entity_id: &str) -> u32 {
+        #[allow(clippy::cast_possible_truncation)]
+        let shard = (fnv(entity_id.as_bytes()) % u64::from(self.settings.number_of_shards)) as u32;
+        shard
+    }
+
+    fn owner(&self, shard: u32) -> SocketAddr {
+        owner(&self.members, shard).unwrap_or_else(|| self.cluster.self_addr())
+    }
+
+    fn route(
+        &mut self,
+        ctx: &Context<ShardRegionMsg<A::Msg>>,
+        msg: EntityMsg<A::Msg>,
+        sender: Sender,
+        hops: u32,
+    ) {
+        let shard = self.shard_id(&msg.id);
+        let owner = self.owner(shard);
+
+        if owner == self.cluster.self_addr() {
+            self.deliver(ctx, shard, msg, sender);
+            return;
+        }
+
+        if hops >= MAX_HOPS {
+            warn!(
+                ctx.system.log(),
+                "Dropping message for entity {}: routed {} times without reaching its shard",
+                msg.id,
+                hops
+            );
+            return;
+        }
+
+        match ctx.system.serialization().serialize(&msg.msg) {
+            Ok(serialized) => {
+                let envelope = ShardEnvelope::Tell {
+                    entity_id: msg.id,
+                    msg: serialized,
+                    hops: hops + 1,
+                };
+                self.send(ctx, owner, envelope);
+            }
+            Err(e) => warn!(
+                ctx.system.log(),
+                "Failed to route message to entity {}: {}", msg.id, e
+            ),
+        }
+    }
+
+    /// Deliver a message to a shard owned by this node. Messages are
+    /// buffered while the shard is handed over.
+    fn deliver(
+        &mut self,
+        ctx: &Context<ShardRegionMsg<A::Msg>>,
+        shard: u32,
+        msg: EntityMsg<A::Msg>,
+        sender: Sender,
+    ) {
+        if !self.shards.contains_key(&shard) {
+            // the shard is started once the member that would own it
+            // without this node has handed it off
+            let self_addr = self.cluster.self_addr();
+            let others: Vec<_> = self
+                .members
+                .iter()
+                .copied()
+                .filter(|addr| *addr != self_addr)
+                .collect();
+
+            match owner(&others, shard) {
+                Some(previous_owner) => self.take_over(ctx, shard, previous_owner),
+                None => self.start_shard(ctx, shard),
+            }
+        }
+
+        match self.shards.get_mut(&shard) {
+            Some(Shard::Running(manager)) => manager.tell(msg, sender),
+            Some(Shard::TakingOver { buffer, .. } | Shard::HandingOff(buffer)) => {
+                if buffer.len() < self.settings.buffer_size {
+                    buffer.push((msg, sender));
+                } else {
+                    warn!(
+                        ctx.system.log(),
+                        "Shard {} of {} buffer is full. Dropping message", shard, self.type_name
+                    );
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn start_shard(&mut self, ctx: &Context<ShardRegionMsg<A::Msg>>, shard: u32) {
+        match ctx.actor_of::<EntityManager<A>>(&format!("shard-{}", shard)) {
+            Ok(manager) => {
+                self.shards.insert(shard, Shard::Running(manager));
+            }
+            Err(e) => warn!(ctx.system.log(), "Failed to start shard {}: {}", shard, e),
+        }
+    }
+
+    /// Route buffered messages again, once this region has handled
+    /// its current messages
+    fn reroute(ctx: &Context<ShardRegionMsg<A::Msg>>, buffer: Buffer<A::Msg>) {
+        for (msg, sender) in buffer {
+            ctx.myself.tell(ShardRegionMsg::Tell(msg), sender);
+        }
+    }
+
+    /// Send to the shard region of another node
+    fn send(
+        &mut self,
+        ctx: &Context<ShardRegionMsg<A::Msg>>,
+        addr: SocketAddr,
+        envelope: ShardEnvelope,
+    ) {
+        if let Some(region) = self.regions.get(&addr) {
+            region.tell(envelope, None);
+            return;
+        }
+
+        let path = format!("/system/sharding/{}/inbound", self.type_name);
+        let address =
+            ActorAddress::new(&ctx.system.name(), &addr.to_string(), ActorPath::new(&path));
+
+        match self.cluster.remoting().actor_ref::<ShardEnvelope>(&address) {
+            Ok(region) => {
+                region.tell(envelope, None);
+                self.regions.insert(addr, region);
+            }
+            Err(e) => warn!(
+                ctx.system.log(),
+                "Failed to reach shard region {}: {}", address, e
+            ),
+        }
+    }
+
+    /// Hand off the local shards that are now owned by other members
+    fn rebalance(&mut self, ctx: &Context<ShardRegionMsg<A::Msg>>) {
+        self.members = self
+            .cluster
+            .members()
+            .into_iter()
+            .filter(|m| m.status == MemberStatus::Up)
+            .map(|m| m.addr)
+            .collect();
+
+        let self_addr = self.cluster.self_addr();
+        let moved: Vec<_> = self
+            .shards
+            .keys()
+            .copied()
+            .filter(|shard| self.owner(*shard) != self_addr)
+            .collect();
+
+        for shard in moved {
+            match self.shards.remove(&shard) {
+                Some(Shard::Running(manager)) => {
+                    debug!(
+                        ctx.system.log(),
+                        "Handing off shard {} of {}", shard, self.type_name
+                    );
+                    ctx.stop(&manager);
+                    self.shards.insert(shard, Shard::HandingOff(Vec::new()));
+                }
+                Some(Shard::TakingOver { buffer, .. }) => Self::reroute(ctx, buffer),
+                Some(handing_off) => {
+                    self.shards.insert(shard, handing_off);
+                }
+                None => {}
+            }
+        }
+
+        let requests: Vec<_> = self.hand_off_requests.iter().copied().collect();
+        for shard in requests {
+            self.hand_off(ctx, shard);
+        }
+
+        let members = &self.members;
+        self.regions.retain(|addr, region| {
+            let keep = members.contains(addr);
+            if !keep {
+                ctx.stop(&*region);
+            }
+            keep
+        });
+    }
+
+    /// Ask the previous owner of a shard to stop it. The shard is started
+    /// when the previous owner has stopped it, or after
+    /// `sharding.hand_off_timeout_millis` if it doesn't answer.
+    fn take_over(
+        &mut self,
+        ctx: &Context<ShardRegionMsg<A::Msg>>,
+        shard: u32,
+        previous_owner: SocketAddr,
+    ) {
+        debug!(
+            ctx.system.log(),
+            "Taking over shard {} of {} from {}", shard, self.type_name, previous_owner
+        );
+        self.attempts += 1;
+        let attempt = self.attempts;
+        self.shards.insert(
+            shard,
+            Shard::TakingOver {
+                attempt,
+                buffer: Vec::new(),
+            },
+        );
+
+        self.send(ctx, previous_owner, ShardEnvelope::HandOff(shard));
+        ctx.schedule_once(
+            self.settings.hand_off_timeout,
+            ctx.myself(),
+            None,
+            ShardRegionMsg::HandOffTimeout { shard, attempt },
+        );
+    }
+
+    /// Start a shard that was taken over, delivering the messages
+    /// buffered meanwhile
+    fn taken_over(&mut self, ctx: &Context<ShardRegionMsg<A::Msg>>, shard: u32) {
+        if let Some(Shard::TakingOver { buffer, .. }) = self.shards.remove(&shard) {
+            self.start_shard(ctx, shard);
+
+            if let Some(Shard::Running(manager)) = self.shards.get(&shard) {
+                for (msg, sender) in buffer {
+                    manager.tell(msg, sender);
+                }
+            }
+        }
+    }
+
+    /// Answer another node's request for a shard once this node
+    /// no longer runs it
+    fn hand_off(&mut self, ctx: &Context<ShardRegionMsg<A::Msg>>, shard: u32) {
+        let owner = self.owner(shard);
+
+        if owner == self.cluster.self_addr() {
+            // this node doesn't know yet that the shard has moved
+            self.hand_off_requests.insert(shard);
+        } else if !self.shards.contains_key(&shard) {
+            self.hand_off_requests.remove(&shard);
+            self.send(ctx, owner, ShardEnvelope::HandOffDone(shard));
+        }
+    }
+}
+
+impl<A> Actor for ShardRegion<A>
+where
+    A: ActorFactoryArgs<String>,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    type Msg = ShardRegionMsg<A::Msg>;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        if let Err(e) = ctx.actor_of_args::<ShardInbound<A::Msg>, _>("inbound", ctx.myself()) {
+            warn!(
+                ctx.system.log(),
+                "Failed to start shard region inbound: {}", e
+            );
+        }
+
+        self.cluster.events().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+        self.rebalance(ctx);
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        match msg {
+            ShardRegionMsg::Tell(msg) => self.route(ctx, msg, sender, 0),
+            ShardRegionMsg::Deliver { msg, hops } => self.route(ctx, msg, sender, hops),
+            ShardRegionMsg::HandOff(shard) => self.hand_off(ctx, shard),
+            ShardRegionMsg::HandOffDone(shard) => self.taken_over(ctx, shard),
+            ShardRegionMsg::HandOffTimeout { shard, attempt } => match self.shards.get(&shard) {
+                Some(Shard::TakingOver {
+                    attempt: current, ..
+                }) if *current == attempt => {
+                    warn!(
+                        ctx.system.log(),
+                        "Shard {} of {} was not handed off in time", shard, self.type_name
+                    );
+                    self.taken_over(ctx, shard);
+                }
+                // a timeout of an earlier attempt
+                _ => {}
+            },
+            ShardRegionMsg::Member(_) => self.rebalance(ctx),
+        }
+    }
+
+    // Shards are children of the region, so the region receives
+    // ActorTerminated when a shard stops
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg else {
+            return;
+        };
+        let Some(shard) = terminated
+            .actor
+            .name()
+            .strip_prefix("shard-")
+            .and_then(|shard| shard.parse().ok())
+        else {
+            return;
+        };
+
+        // a shard that stops while running is started again
+        // by its next message
+        let buffer = match self.shards.remove(&shard) {
+            Some(Shard::HandingOff(buffer)) => buffer,
+            Some(Shard::Running(_)) => Vec::new(),
+            Some(taking_over) => {
+                self.shards.insert(shard, taking_over);
+                return;
+            }
+            None => return,
+        };
+
+        // the new owner is told before the buffered messages reach it
+        let owner = self.owner(shard);
+        if owner != self.cluster.self_addr() {
+            debug!(
+                ctx.system.log(),
+                "Handed off shard {} of {} to {}", shard, self.type_name, owner
+            );
+            self.hand_off_requests.remove(&shard);
+            self.send(ctx, owner, ShardEnvelope::HandOffDone(shard));
+        }
+
+        // the terminated shard is removed from the region's children after
+        // this function returns, so the buffered messages are routed again
+        // from the mailbox
+        Self::reroute(ctx, buffer);
+    }
+}
+
+/// Receives the messages sent to the region by other nodes
+struct ShardInbound<Msg: Message> {
+    region: ActorRef<ShardRegionMsg<Msg>>,
+    msg: PhantomData<Msg>,
+}
+
+impl<Msg: Message> ActorFactoryArgs<ActorRef<ShardRegionMsg<Msg>>> for ShardInbound<Msg> {
+    fn create_args(region: ActorRef<ShardRegionMsg<Msg>>) -> Self {
+        Self {
+            region,
+            msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg: Message> Actor for ShardInbound<Msg> {
+    type Msg = ShardEnvelope;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, envelope: Self::Msg, _sender: Sender) {
+        let msg = match envelope {
+            ShardEnvelope::Tell {
+                entity_id,
+                msg,
+                hops,
+            } => match ctx.system.serialization().deserialize::<Msg>(&msg) {
+                Ok(msg) => ShardRegionMsg::Deliver {
+                    msg: EntityMsg { id: entity_id, msg },
+                    hops,
+                },
+                Err(e) => {
+                    warn!(
+                        ctx.system.log(),
+                        "Failed to receive message for entity {}: {}", entity_id, e
+                    );
+                    return;
+                }
+            },
+            ShardEnvelope::HandOff(shard) => ShardRegionMsg::HandOff(shard),
+            ShardEnvelope::HandOffDone(shard) => ShardRegionMsg::HandOffDone(shard),
+        };
+
+        self.region.tell(msg, None);
+    }
+}
+
+/// The member with the highest score for the shard owns it
+fn owner(members: &[SocketAddr], shard: u32) -> Option<SocketAddr> {
+    members
+        .iter()
+        .max_by_key(|addr| fnv(format!("{}/{}", shard, addr).as_bytes()))
+        .copied()
+}
+
+/// FNV-1a, used because shard ids and owners must be the same
+/// on every node
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+struct ShardingSettings {
+    number_of_shards: u32,
+    hand_off_timeout: Duration,
+    buffer_size: usize,
+}
+
+impl<'a> From<&'a Config> for ShardingSettings {
+    fn from(config: &Config) -> Self {
+        Self {
+            number_of_shards: config
+                .get::<u32>("sharding.number_of_shards")
+                .unwrap()
+                .max(1),
+            hand_off_timeout: Duration::from_millis(
+                config
+                    .get::<u64>("sharding.hand_off_timeout_millis")
+                    .unwrap(),
+            ),
+            buffer_size: config.get::<usize>("sharding.buffer_size").unwrap(),
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use std::{marker::PhantomData, net::SocketAddr, time::Duration};
+
+use config::Config;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{debug, warn};
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, ActorReference, BasicActorRef, Sender, Tell};
+use crate::{
+    actor::{
+        Actor, ActorAddress, ActorFactory, ActorFactoryArgs, ActorPath, All, Context, CreateError,
+        Props, Subscribe,
+    },
+    cluster::{Cluster, ClusterError, ClusterEvent, Member},
+    system::{SerializedMessage, SystemEvent, SystemMsg, Timer},
+    Message,
+};
+
+/// A cluster singleton is an actor that runs on exactly one member
+/// of the cluster, the oldest.
+///
+/// Each node starts the singleton with the same name. The singleton's
+/// manager on the oldest member creates the actor, and the `ActorRef`
+/// returned on every node is a proxy that forwards messages to wherever
+/// the singleton currently runs:
+///
+/// ```ignore
+/// let singletons = ClusterSingleton::new(&cluster)?;
+/// let scheduler = singletons.start::<Scheduler>("scheduler")?;
+///
+/// scheduler.tell(Schedule::new(job), None);
+/// ```
+///
+/// When the oldest member leaves the cluster it stops the singleton and
+/// hands it over to the next oldest member, which starts it once the
+/// previous singleton has stopped. If the oldest member is removed
+/// because it is unreachable, the next oldest member starts the singleton
+/// after `singleton.hand_over_timeout_millis`.
+///
+/// Messages sent while the singleton is being handed over are buffered,
+/// up to `singleton.buffer_size` messages, as are messages forwarded back
+/// and forth while nodes disagree on the oldest member. The singleton's message type
+/// must be registered with the serialization registry on every node.
+/// The sender of a message forwarded to another node is not sent.
+#[derive(Clone)]
+pub struct ClusterSingleton {
+    cluster: Cluster,
+    guardian: BasicActorRef,
+}
+
+impl ClusterSingleton {
+    pub fn new(cluster: &Cluster) -> Result<Self, ClusterError> {
+        let sys = cluster.remoting().system();
+        sys.register_internal::<SingletonEnvelope>(SINGLETON_ENVELOPE_MANIFEST)?;
+
+        let guardian = sys.sys_actor_of::<SingletonGuardian>("singletons")?;
+
+        Ok(Self {
+            cluster: cluster.clone(),
+            guardian: guardian.into(),
+        })
+    }
+
+    /// Start the manager of the singleton named `name` on this node and
+    /// return a proxy to the singleton.
+    ///
+    /// The manager is created at `/system/singletons/<name>`. A singleton
+    /// with the same name must be started on every node.
+    pub fn start<A>(&self, name: &str) -> Result<ActorRef<A::Msg>, CreateError>
+    where
+        A: ActorFactory,
+        A::Msg: Serialize + DeserializeOwned,
+    {
+        let sys = self.cluster.remoting().system();
+        let props = Props::new_args(
+            SingletonManager::<A>::create_args,
+            (
+                self.cluster.clone(),
+                name.to_string(),
+                SingletonSettings::from(&sys.config()),
+            ),
+        );
+        let manager = sys
+            .provider
+            .create_actor(props, name, &self.guardian, sys)?;
+
+        let props = Props::new_args(SingletonProxy::create_args, manager.clone());
+        sys.provider
+            .create_actor(props, "proxy", &manager.into(), sys)
+    }
+}
+
+/// The parent of the singleton managers on a node
+#[derive(Default)]
+struct SingletonGuardian;
+
+impl Actor for SingletonGuardian {
+    type Msg = ();
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {}
+}
+
+const SINGLETON_ENVELOPE_MANIFEST: &str = "riker.cluster.SingletonEnvelope";
+
+/// The number of times a message is forwarded between managers before
+/// it's buffered until membership changes. Messages are forwarded more
+/// than once only while the managers disagree on the oldest member.
+const MAX_HOPS: u32 = 3;
+
+/// A message sent to the singleton manager of another node
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SingletonEnvelope {
+    /// A message for the singleton, forwarded `hops` times so far
+    Tell { msg: SerializedMessage, hops: u32 },
+
+    /// The previous oldest member has stopped its singleton
+    HandOverDone,
+}
+
+#[derive(Clone, Debug)]
+enum SingletonMsg<Msg: Message> {
+    /// A message for the singleton, wherever it runs
+    Tell(Msg),
+
+    /// A message for the singleton forwarded by another node
+    Deliver {
+        msg: Msg,
+        hops: u32,
+    },
+
+    HandOverDone,
+    HandOverTimeout {
+        attempt: u64,
+    },
+    Member(ClusterEvent),
+}
+
+impl<Msg: Message> From<ClusterEvent> for SingletonMsg<Msg> {
+    fn from(evt: ClusterEvent) -> Self {
+        Self::Member(evt)
+    }
+}
+
+enum State<Msg: Message> {
+    /// The singleton runs on another member, or this node is not up
+    Idle,
+
+    /// This node is the oldest member and waits for the previous
+    /// oldest member to hand over the singleton
+    TakingOver {
+        attempt: u64,
+    },
+
+    Running(ActorRef<Msg>),
+
+    /// The singleton is stopping because this node is no longer the
+    /// oldest member
+    HandingOver,
+}
+
+/// Starts the singleton when this node is the oldest member and routes
+/// the messages sent to the proxy
+struct SingletonManager<A: Actor> {
+    cluster: Cluster,
+    name: String,
+    settings: SingletonSettings,
+    oldest: Option<SocketAddr>,
+    state: State<A::Msg>,
+    attempts: u64,
+    hand_over_done: bool,
+    buffer: Vec<(A::Msg, Sender)>,
+    remote: Option<(SocketAddr, ActorRef<SingletonEnvelope>)>,
+}
+
+type ManagerArgs = (Cluster, String, SingletonSettings);
+
+impl<A> ActorFactoryArgs<ManagerArgs> for SingletonManager<A>
+where
+    A: ActorFactory,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    fn create_args((cluster, name, settings): ManagerArgs) -> Self {
+        Self {
+            cluster,
+            name,
+            settings,
+            oldest: None,
+            state: State::Idle,
+            attempts: 0,
+            hand_over_done: false,
+            buffer: Vec::new(),
+            remote: None,
+        }
+    }
+}
+
+impl<A> SingletonManager<A>
+where
+    A: ActorFactory,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    fn oldest(&self) -> Option<SocketAddr> {
+        self.cluster
+            .members()
+            .into_iter()
+            .fold(None, |oldest: Option<Member>, m| match oldest {
+                Some(oldest) if oldest.is_older_than(&m) => Some(oldest),
+                _ => Some(m),
+            })
+            .map(|m| m.addr)
+    }
+
+    fn membership_changed(&mut self, ctx: &Context<SingletonMsg<A::Msg>>) {
+        let previous = self.oldest;
+        self.oldest = self.oldest();
+
+        let self_addr = self.cluster.self_addr();
+        let is_oldest = self.cluster.is_up() && self.oldest == Some(self_addr);
+
+        match self.state {
+            State::Idle if is_oldest => match previous {
+                Some(previous) if previous != self_addr && !self.hand_over_done => {
+                    debug!(ctx.system.log(), "Taking over singleton {}", self.name);
+                    self.attempts += 1;
+                    let attempt = self.attempts;
+                    self.state = State::TakingOver { attempt };
+                    let timeout = self.settings.hand_over_timeout;
+                    let msg = SingletonMsg::HandOverTimeout { attempt };
+                    ctx.schedule_once(timeout, ctx.myself(), None, msg);
+                }
+                _ => self.start(ctx),
+            },
+            State::TakingOver { .. } if !is_oldest => self.state = State::Idle,
+            State::Running(ref singleton) if !is_oldest => {
+                debug!(ctx.system.log(), "Handing over singleton {}", self.name);
+                let singleton = singleton.clone();
+                ctx.stop(&singleton);
+                self.state = State::HandingOver;
+            }
+            _ => {}
+        }
+
+        self.flush(ctx);
+    }
+
+    fn start(&mut self, ctx: &Context<SingletonMsg<A::Msg>>) {
+        match ctx.actor_of::<A>("singleton") {
+            Ok(singleton) => {
+                debug!(ctx.system.log(), "Started singleton {}", self.name);
+                self.state = State::Running(singleton);
+                self.hand_over_done = false;
+                self.flush(ctx);
+            }
+            Err(e) => {
+                warn!(
+                    ctx.system.log(),
+                    "Failed to start singleton {}: {}", self.name, e
+                );
+                self.state = State::Idle;
+            }
+        }
+    }
+
+    /// Route a message that was forwarded `hops` times so far
+    fn route(
+        &mut self,
+        ctx: &Context<SingletonMsg<A::Msg>>,
+        msg: A::Msg,
+        sender: Sender,
+        hops: u32,
+    ) {
+        match self.state {
+            State::Running(ref singleton) => singleton.send_msg(msg, sender),
+            State::Idle => match self.oldest {
+                Some(oldest) if oldest != self.cluster.self_addr() && hops < MAX_HOPS => {
+                    self.forward(ctx, oldest, &msg, hops + 1);
+                }
+                _ => self.stash(ctx, msg, sender),
+            },
+            State::TakingOver { .. } | State::HandingOver => self.stash(ctx, msg, sender),
+        }
+    }
+
+    fn stash(&mut self, ctx: &Context<SingletonMsg<A::Msg>>, msg: A::Msg, sender: Sender) {
+        if self.buffer.len() < self.settings.buffer_size {
+            self.buffer.push((msg, sender));
+        } else {
+            warn!(
+                ctx.system.log(),
+                "Singleton {} buffer is full. Dropping message", self.name
+            );
+        }
+    }
+
+    fn flush(&mut self, ctx: &Context<SingletonMsg<A::Msg>>) {
+        let buffer = std::mem::take(&mut self.buffer);
+
+        for (msg, sender) in buffer {
+            self.route(ctx, msg, sender, 0);
+        }
+    }
+
+    fn forward(
+        &mut self,
+        ctx: &Context<SingletonMsg<A::Msg>>,
+        addr: SocketAddr,
+        msg: &A::Msg,
+        hops: u32,
+    ) {
+        match ctx.system.serialization().serialize(msg) {
+            Ok(msg) => self.send(ctx, addr, SingletonEnvelope::Tell { msg, hops }),
+            Err(e) => warn!(
+                ctx.system.log(),
+                "Failed to forward message to singleton {}: {}", self.name, e
+            ),
+        }
+    }
+
+    /// Send to the singleton manager of another node
+    fn send(
+        &mut self,
+        ctx: &Context<SingletonMsg<A::Msg>>,
+        addr: SocketAddr,
+        envelope: SingletonEnvelope,
+    ) {
+        match self.remote {
+            Some((remote_addr, ref manager)) if remote_addr == addr => {
+                manager.tell(envelope, None);
+                return;
+            }
+            Some((_, ref manager)) => ctx.stop(manager),
+            None => {}
+        }
+
+        let path = format!("/system/singletons/{}/inbound", self.name);
+        let address =
+            ActorAddress::new(&ctx.system.name(), &addr.to_string(), ActorPath::new(&path));
+
+        self.remote = match self
+            .cluster
+            .remoting()
+            .actor_ref::<SingletonEnvelope>(&address)
+        {
+            Ok(manager) => {
+                manager.tell(envelope, None);
+                Some((addr, manager))
+            }
+            Err(e) => {
+                warn!(
+                    ctx.system.log(),
+                    "Failed to reach singleton {}: {}", address, e
+                );
+                None
+            }
+        };
+    }
+}
+
+impl<A> Actor for SingletonManager<A>
+where
+    A: ActorFactory,
+    A::Msg: Serialize + DeserializeOwned,
+{
+    type Msg = SingletonMsg<A::Msg>;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        if let Err(e) = ctx.actor_of_args::<SingletonInbound<A::Msg>, _>("inbound", ctx.myself()) {
+            warn!(ctx.system.log(), "Failed to start singleton inbound: {}", e);
+        }
+
+        self.cluster.events().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+        self.membership_changed(ctx);
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        match msg {
+            SingletonMsg::Tell(msg) => self.route(ctx, msg, sender, 0),
+            // a message forwarded by another node is forwarded again if this
+            // node knows the singleton runs elsewhere. A message forwarded
+            // too many times, while the nodes disagree on the oldest member,
+            // is buffered until membership changes.
+            SingletonMsg::Deliver { msg, hops } => self.route(ctx, msg, sender, hops),
+            SingletonMsg::HandOverDone => match self.state {
+                State::TakingOver { .. } => self.start(ctx),
+                // the hand over can complete before this node knows
+                // that the previous oldest member left
+                _ => self.hand_over_done = true,
+            },
+            SingletonMsg::HandOverTimeout { attempt } => match self.state {
+                State::TakingOver { attempt: current } if current == attempt => self.start(ctx),
+                // a timeout of an earlier take over
+                _ => {}
+            },
+            SingletonMsg::Member(evt) => {
+                debug!(
+                    ctx.system.log(),
+                    "Singleton {} received {:?}", self.name, evt
+                );
+                self.membership_changed(ctx);
+            }
+        }
+    }
+
+    // The singleton is a child of the manager, so the manager receives
+    // ActorTerminated when the singleton stops
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            if terminated.actor.name() != "singleton" {
+                return;
+            }
+
+            if matches!(self.state, State::HandingOver) {
+                self.state = State::Idle;
+
+                match self.oldest() {
+                    Some(oldest) if oldest != self.cluster.self_addr() => {
+                        self.send(ctx, oldest, SingletonEnvelope::HandOverDone);
+                    }
+                    _ => {}
+                }
+                self.flush(ctx);
+            }
+        }
+    }
+}
+
+/// The proxy `ActorRef` returned by `ClusterSingleton::start`
+struct SingletonProxy<Msg: Message> {
+    manager: ActorRef<SingletonMsg<Msg>>,
+}
+
+impl<Msg: Message> ActorFactoryArgs<ActorRef<SingletonMsg<Msg>>> for SingletonProxy<Msg> {
+    fn create_args(manager: ActorRef<SingletonMsg<Msg>>) -> Self {
+        Self { manager }
+    }
+}
+
+impl<Msg: Message> Actor for SingletonProxy<Msg> {
+    type Msg = Msg;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.manager.send_msg(SingletonMsg::Tell(msg), sender);
+    }
+}
+
+/// Receives the messages sent to the manager by other nodes
+struct SingletonInbound<Msg: Message> {
+    manager: ActorRef<SingletonMsg<Msg>>,
+    msg: PhantomData<Msg>,
+}
+
+impl<Msg: Message> ActorFactoryArgs<ActorRef<SingletonMsg<Msg>>> for SingletonInbound<Msg> {
+    fn create_args(manager: ActorRef<SingletonMsg<Msg>>) -> Self {
+        Self {
+            manager,
+            msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg: Message> Actor for SingletonInbound<Msg> {
+    type Msg = SingletonEnvelope;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, envelope: Self::Msg, _sender: Sender) {
+        match envelope {
+            SingletonEnvelope::Tell { msg, hops } => {
+                match ctx.system.serialization().deserialize(&msg) {
+                    Ok(msg) => self.manager.tell(SingletonMsg::Deliver { msg, hops }, None),
+                    Err(e) => warn!(
+                        ctx.system.log(),
+                        "Failed to receive message for singleton: {}", e
+                    ),
+                }
+            }
+            SingletonEnvelope::HandOverDone => {
+                self.manager.tell(SingletonMsg::HandOverDone, None);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SingletonSettings {
+    hand_over_timeout: Duration,
+    buffer_size: usize,
+}
+
+impl<'a> From<&'a Config> for SingletonSettings {
+    fn from(config: &Config) -> Self {
+        Self {
+            hand_over_timeout: Duration::from_millis(
+                config
+                    .get::<u64>("singleton.hand_over_timeout_millis")
+                    .unwrap(),
+            ),
+            buffer_size: config.get::<usize>("singleton.buffer_size").unwrap(),
+        }
+    }
+}
@@ -1,8 +1,8 @@
+use std::any::Any;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
-use std::thread;
 
 use config::Config;
 
@@ -31,6 +31,8 @@ pub trait AnySender: Send + Sync {
     fn set_sched(&self, b: bool);
 
     fn is_sched(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
 }
 
 #[derive(Clone)]
@@ -81,6 +83,10 @@ where
     fn is_sched(&self) -> bool {
         self.is_scheduled()
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 unsafe impl<Msg: Message> Send for MailboxSender<Msg> {}
@@ -182,23 +188,17 @@ where
     (sender, sys_sender, mailbox)
 }
 
-pub fn run_mailbox<A>(mbox: &Mailbox<A::Msg>, ctx: &Context<A::Msg>, mut dock: Dock<A>)
+pub async fn run_mailbox<A>(mbox: &Mailbox<A::Msg>, ctx: &Context<A::Msg>, mut dock: Dock<A>)
 where
     A: Actor,
 {
-    let _sen = Sentinel {
-        actor: ctx.myself().into(),
-        parent: ctx.myself().parent(),
-        mbox: mbox.clone(),
-    };
-
     let mut actor = dock.actor.lock().unwrap().take();
     let cell = &mut dock.cell;
 
     process_sys_msgs(mbox, ctx, cell, &mut actor);
 
     if actor.is_some() && !mbox.is_suspended() {
-        process_msgs(mbox, ctx, cell, &mut actor);
+        process_msgs(mbox, ctx, cell, &mut actor).await;
     }
 
     process_sys_msgs(mbox, ctx, cell, &mut actor);
@@ -216,7 +216,7 @@ where
     }
 }
 
-fn process_msgs<A>(
+async fn process_msgs<A>(
     mbox: &Mailbox<A::Msg>,
     ctx: &Context<A::Msg>,
     cell: &ExtendedCell<A::Msg>,
@@ -231,7 +231,10 @@ fn process_msgs<A>(
             if let Ok(msg) = mbox.try_dequeue() {
                 match (msg.msg, msg.sender) {
                     (msg, sender) => {
-                        actor.as_mut().unwrap().recv(ctx, msg, sender);
+                        if let Some(handler) = actor.as_mut().unwrap().recv_future(ctx, msg, sender)
+                        {
+                            handler.await;
+                        }
                         process_sys_msgs(mbox, ctx, cell, actor);
                     } // (ActorMsg::Identify, sender) => handle_identify(sender, cell),
                 }
@@ -281,6 +284,7 @@ fn handle_init<A>(
     A: Actor,
 {
     actor.as_mut().unwrap().pre_start(ctx);
+    cell.recover(ctx, actor.as_mut().unwrap());
     mbox.set_suspended(false);
 
     if cell.is_user() {
@@ -292,10 +296,6 @@ fn handle_init<A>(
         );
     }
 
-    // if persistence is not configured then set as not suspended
-    // if cell.load_events(actor) {
-    //     mbox.set_suspended(false);
-    // }
     actor.as_mut().unwrap().post_start(ctx);
 }
 
@@ -326,29 +326,22 @@ fn handle_evt<A>(
     }
 }
 
-struct Sentinel<Msg: Message> {
-    parent: BasicActorRef,
-    actor: BasicActorRef,
-    mbox: Mailbox<Msg>,
-}
-
-impl<Msg> Drop for Sentinel<Msg>
+/// Reports the failure of an actor that panicked while running its mailbox
+pub fn mailbox_failed<Msg>(mbox: &Mailbox<Msg>, ctx: &Context<Msg>)
 where
     Msg: Message,
 {
-    fn drop(&mut self) {
-        if thread::panicking() {
-            // Suspend the mailbox to prevent further message processing
-            self.mbox.set_suspended(true);
+    // Suspend the mailbox to prevent further message processing
+    mbox.set_suspended(true);
 
-            // There is no actor to park but kernel still needs to mark as no longer scheduled
-            // self.kernel.park_actor(self.actor.uri.uid, None);
-            self.mbox.set_scheduled(false);
+    // There is no actor to park but kernel still needs to mark as no longer scheduled
+    // self.kernel.park_actor(self.actor.uri.uid, None);
+    mbox.set_scheduled(false);
 
-            // Message the parent (this failed actor's supervisor) to decide how to handle the failure
-            self.parent.sys_tell(SystemMsg::Failed(self.actor.clone()));
-        }
-    }
+    // Message the parent (this failed actor's supervisor) to decide how to handle the failure
+    ctx.myself()
+        .parent()
+        .sys_tell(SystemMsg::Failed(ctx.myself().into()));
 }
 
 pub fn flush_to_deadletters<Msg>(mbox: &Mailbox<Msg>, actor: &BasicActorRef, sys: &ActorSystem)
@@ -7,10 +7,10 @@ use crate::actor::actor_cell::ExtendedCell;
 use crate::actor::{Actor, ActorProducer, BoxActorProd, Context, CreateError};
 use crate::actor_ref::{ActorRef, ActorReference, BasicActorRef};
 use crate::kernel::kernel_ref::KernelRef;
-use crate::kernel::mailbox::{flush_to_deadletters, run_mailbox, Mailbox};
+use crate::kernel::mailbox::{flush_to_deadletters, mailbox_failed, run_mailbox, Mailbox};
 use crate::system::{ActorRestarted, ActorSystem, ActorTerminated, SystemMsg};
 use crate::Message;
-use futures::{channel::mpsc::channel, task::SpawnExt, StreamExt};
+use futures::{channel::mpsc::channel, task::SpawnExt, FutureExt, StreamExt};
 use slog::warn;
 use std::{
     panic::{catch_unwind, AssertUnwindSafe},
@@ -79,9 +79,11 @@ where
                     let mb = mailbox.clone();
                     let d = dock.clone();
 
-                    let _ =
-                        std::panic::catch_unwind(AssertUnwindSafe(|| run_mailbox(&mb, &ctx, d)));
-                    //.unwrap();
+                    // panics, including in the futures of async actors, fail the actor
+                    let run = AssertUnwindSafe(run_mailbox(&mb, &ctx, d));
+                    if run.catch_unwind().await.is_err() {
+                        mailbox_failed(&mb, &ctx);
+                    }
                 }
                 KernelMsg::RestartActor => {
                     restart_actor(&dock, actor_ref.clone().into(), &props, &child_sys);
@@ -109,6 +111,8 @@ fn restart_actor<A>(
 ) where
     A: Actor,
 {
+    actor_ref.cell.abort_restarted_tasks();
+
     let mut a = dock.actor.lock().unwrap();
     if let Ok(actor) = start_actor(props) {
         *a = Some(actor);
@@ -124,6 +128,7 @@ where
     Msg: Message,
 {
     sys.provider.unregister(actor_ref.path());
+    actor_ref.cell.abort_tasks();
     flush_to_deadletters(mbox, &actor_ref, sys);
     sys.publish_event(
         ActorTerminated {
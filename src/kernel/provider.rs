@@ -1,5 +1,6 @@
 use slog::trace;
 use std::{
+    any::TypeId,
     collections::HashSet,
     sync::{Arc, Mutex},
 };
@@ -146,6 +147,7 @@ fn root(sys: &ActorSystem) -> BasicActorRef {
         sys,
         // None, // old perfaconf
         Arc::new(sender),
+        TypeId::of::<SystemMsg>(),
         sys_sender,
     );
 
@@ -9,7 +9,9 @@
 mod validate;
 
 pub mod actor;
+pub mod cluster;
 pub mod kernel;
+pub mod remote;
 pub mod system;
 pub use crate::actor::actor_ref::*;
 pub use crate::actor::*;
@@ -38,6 +40,37 @@ pub fn load_config() -> Config {
     cfg.set_default("mailbox.msg_process_limit", 1000).unwrap();
     cfg.set_default("dispatcher.pool_size", 4).unwrap();
     cfg.set_default("scheduler.frequency_millis", 50).unwrap();
+    cfg.set_default("cqrs.sleep_after_secs", 120).unwrap();
+    cfg.set_default("persistence.query_interval_millis", 100)
+        .unwrap();
+    cfg.set_default("remote.reconnect_interval_millis", 500)
+        .unwrap();
+    cfg.set_default("remote.reconnect_attempts", 10).unwrap();
+    cfg.set_default("remote.max_frame_bytes", 8_388_608)
+        .unwrap();
+    cfg.set_default("cluster.seed_nodes", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default("cluster.heartbeat_interval_millis", 1000)
+        .unwrap();
+    cfg.set_default("cluster.gossip_interval_millis", 1000)
+        .unwrap();
+    cfg.set_default("cluster.phi_threshold", 8.0).unwrap();
+    cfg.set_default("cluster.max_sample_size", 1000).unwrap();
+    cfg.set_default("cluster.min_std_deviation_millis", 100)
+        .unwrap();
+    cfg.set_default("cluster.acceptable_heartbeat_pause_millis", 3000)
+        .unwrap();
+    cfg.set_default("cluster.remove_unreachable_after_millis", 10000)
+        .unwrap();
+    cfg.set_default("sharding.number_of_shards", 100).unwrap();
+    cfg.set_default("sharding.hand_off_timeout_millis", 2000)
+        .unwrap();
+    cfg.set_default("sharding.buffer_size", 1000).unwrap();
+    cfg.set_default("singleton.hand_over_timeout_millis", 2000)
+        .unwrap();
+    cfg.set_default("singleton.buffer_size", 1000).unwrap();
+    cfg.set_default("pubsub.gossip_interval_millis", 1000)
+        .unwrap();
 
     // load the system config
     // riker.toml contains settings for anything related to the actor framework and its modules
@@ -0,0 +1,630 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    marker::PhantomData,
+    net::SocketAddr,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use futures::{future::BoxFuture, FutureExt};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{debug, warn};
+use uuid::Uuid;
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, ActorReference, BasicActorRef, Sender, Tell};
+use crate::{
+    actor::{Actor, ActorArgs, ActorFactoryArgs, Context, CreateError, Props, Strategy},
+    remote::{
+        transport::{dead_letter, Frame, WireEnvelope},
+        RemoteMsg,
+    },
+    system::{
+        ActorSystem, PersistenceConf, SerializeError, SerializedMessage, Snapshot, SystemEvent,
+        SystemMsg,
+    },
+    AnyMessage,
+};
+
+const DEPLOY_MSG_MANIFEST: &str = "riker.remote.DeployMsg";
+
+/// Register the messages exchanged by deployers
+pub fn register(sys: &ActorSystem) -> Result<(), SerializeError> {
+    sys.register_internal::<DeployMsg>(DEPLOY_MSG_MANIFEST)
+}
+
+/// Creates a deployed actor as a child of `parent` from serialized args.
+/// The actor sets `failed` when it fails.
+type Factory = Arc<
+    dyn Fn(
+            &ActorSystem,
+            &BasicActorRef,
+            &str,
+            &[u8],
+            &Arc<AtomicBool>,
+        ) -> Result<BasicActorRef, DeployError>
+        + Send
+        + Sync,
+>;
+
+/// The actor factories that other systems can deploy actors with,
+/// by manifest
+#[derive(Clone, Default)]
+pub struct Deployables {
+    factories: Arc<RwLock<HashMap<String, Factory>>>,
+    manifests: Arc<RwLock<HashMap<TypeId, String>>>,
+}
+
+impl Deployables {
+    pub fn register<A, Args>(&self, manifest: &str) -> Result<(), DeployError>
+    where
+        A: ActorFactoryArgs<Args>,
+        Args: ActorArgs + DeserializeOwned,
+    {
+        let factory: Factory = Arc::new(|sys, parent, name, args, failed| {
+            let args: Args =
+                serde_json::from_slice(args).map_err(|e| DeployError::Args(e.to_string()))?;
+            let failed = failed.clone();
+            let create = move |args| Deployed {
+                actor: A::create_args(args),
+                failed: failed.clone(),
+            };
+            let props = Props::new_args(create, args);
+            let actor = sys.provider.create_actor(props, name, parent, sys)?;
+            Ok(actor.into())
+        });
+
+        match self.factories.write().unwrap().entry(manifest.to_string()) {
+            Entry::Occupied(_) => return Err(DeployError::DuplicateManifest(manifest.to_string())),
+            Entry::Vacant(entry) => entry.insert(factory),
+        };
+
+        self.manifests
+            .write()
+            .unwrap()
+            .insert(TypeId::of::<A>(), manifest.to_string());
+        Ok(())
+    }
+
+    pub fn manifest<A: 'static>(&self) -> Result<String, DeployError> {
+        self.manifests
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<A>())
+            .cloned()
+            .ok_or_else(|| DeployError::NotRegistered(type_name::<A>().to_string()))
+    }
+
+    fn factory(&self, manifest: &str) -> Option<Factory> {
+        self.factories.read().unwrap().get(manifest).cloned()
+    }
+}
+
+/// An actor to deploy on another system
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Deployment {
+    pub id: String,
+    pub origin: SocketAddr,
+    pub manifest: String,
+    pub name: String,
+    pub args: Vec<u8>,
+}
+
+/// A message exchanged by the deployers of two systems
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DeployMsg {
+    Deploy(Deployment),
+
+    /// A message for a deployed actor
+    Tell {
+        id: String,
+        msg: SerializedMessage,
+    },
+
+    /// The local reference of a deployed actor stopped
+    Undeploy {
+        id: String,
+    },
+
+    /// A deployed actor terminated, because it stopped or `failed`
+    Terminated {
+        id: String,
+        failed: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum DeployerMsg {
+    /// Deploy an actor on the `target` system for its local reference
+    Deploy {
+        target: SocketAddr,
+        actor: BasicActorRef,
+        deployment: Deployment,
+    },
+
+    /// Send a message to an actor deployed on the `target` system
+    Tell {
+        target: SocketAddr,
+        id: String,
+        msg: SerializedMessage,
+    },
+
+    /// Stop an actor deployed on the `target` system
+    Undeploy { target: SocketAddr, id: String },
+
+    /// An actor deployed on this system terminated
+    Terminated {
+        origin: SocketAddr,
+        id: String,
+        failed: bool,
+    },
+
+    /// A message received from the deployer of another system
+    Received(DeployMsg),
+}
+
+/// Deploys actors on other systems on behalf of their local references
+/// and runs the actors that other systems deploy on this system
+pub struct Deployer {
+    daemon: ActorRef<RemoteMsg>,
+    deployables: Deployables,
+
+    /// The local references of actors deployed on other systems
+    remote: HashMap<String, BasicActorRef>,
+
+    /// The supervisors of actors deployed on this system
+    local: HashMap<String, ActorRef<SerializedMessage>>,
+}
+
+impl ActorFactoryArgs<(ActorRef<RemoteMsg>, Deployables)> for Deployer {
+    fn create_args((daemon, deployables): (ActorRef<RemoteMsg>, Deployables)) -> Self {
+        Self {
+            daemon,
+            deployables,
+            remote: HashMap::new(),
+            local: HashMap::new(),
+        }
+    }
+}
+
+impl Deployer {
+    /// Send to the deployer of the system at `addr`
+    fn send(&self, ctx: &Context<DeployerMsg>, addr: SocketAddr, msg: &DeployMsg) {
+        match ctx.system.serialization().serialize(msg) {
+            Ok(serialized) => {
+                let frame = Frame {
+                    envelope: WireEnvelope {
+                        recipient: "/system/deployer/inbound".to_string(),
+                        msg: serialized,
+                    },
+                    recipient: ctx.myself().into(),
+                    debug: format!("{:?}", msg),
+                };
+                self.daemon.tell(RemoteMsg::Outbound(addr, frame), None);
+            }
+            Err(e) => warn!(ctx.system.log(), "Failed to send deployment message: {}", e),
+        }
+    }
+
+    fn deploy(&mut self, ctx: &Context<DeployerMsg>, deployment: Deployment) {
+        let (id, origin) = (deployment.id.clone(), deployment.origin);
+
+        let Some(factory) = self.deployables.factory(&deployment.manifest) else {
+            warn!(
+                ctx.system.log(),
+                "Failed to deploy actor. Cause: No factory is registered for {}",
+                deployment.manifest
+            );
+            let failed = false;
+            self.send(ctx, origin, &DeployMsg::Terminated { id, failed });
+            return;
+        };
+
+        let args = (factory, deployment, ctx.myself());
+        match ctx.actor_of_args::<DeploySupervisor, _>(&id, args) {
+            Ok(supervisor) => {
+                self.local.insert(id, supervisor);
+            }
+            Err(e) => {
+                warn!(ctx.system.log(), "Failed to deploy actor: {}", e);
+                let failed = false;
+                self.send(ctx, origin, &DeployMsg::Terminated { id, failed });
+            }
+        }
+    }
+
+    fn received(&mut self, ctx: &Context<DeployerMsg>, msg: DeployMsg) {
+        match msg {
+            DeployMsg::Deploy(deployment) => self.deploy(ctx, deployment),
+            DeployMsg::Tell { id, msg } => {
+                if let Some(supervisor) = self.local.get(&id) {
+                    supervisor.tell(msg, None);
+                } else {
+                    let msg = format!("{} for unknown deployment {}", msg.manifest, id);
+                    dead_letter(&ctx.system, msg, ctx.myself().into());
+                }
+            }
+            DeployMsg::Undeploy { id } => {
+                if let Some(supervisor) = self.local.remove(&id) {
+                    ctx.stop(&supervisor);
+                }
+            }
+            DeployMsg::Terminated { id, failed } => {
+                if let Some(actor) = self.remote.remove(&id) {
+                    debug!(
+                        ctx.system.log(),
+                        "Deployed actor {} terminated (failed: {})",
+                        actor.path(),
+                        failed
+                    );
+
+                    // a failure is handled by the local reference's parent,
+                    // as if the local reference had failed
+                    if failed {
+                        let parent = actor.parent();
+                        parent.sys_tell(SystemMsg::Failed(actor));
+                    } else {
+                        ctx.system.stop(&actor);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Actor for Deployer {
+    type Msg = DeployerMsg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        if let Err(e) = ctx.actor_of_args::<DeployInbound, _>("inbound", ctx.myself()) {
+            warn!(ctx.system.log(), "Failed to start deployer inbound: {}", e);
+        }
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            DeployerMsg::Deploy {
+                target,
+                actor,
+                deployment,
+            } => {
+                self.remote.insert(deployment.id.clone(), actor);
+                self.send(ctx, target, &DeployMsg::Deploy(deployment));
+            }
+            DeployerMsg::Tell { target, id, msg } => {
+                self.send(ctx, target, &DeployMsg::Tell { id, msg });
+            }
+            DeployerMsg::Undeploy { target, id } => {
+                if self.remote.remove(&id).is_some() {
+                    self.send(ctx, target, &DeployMsg::Undeploy { id });
+                }
+            }
+            DeployerMsg::Terminated { origin, id, failed } => {
+                if self.local.remove(&id).is_some() {
+                    self.send(ctx, origin, &DeployMsg::Terminated { id, failed });
+                }
+            }
+            DeployerMsg::Received(msg) => self.received(ctx, msg),
+        }
+    }
+}
+
+/// Receives the messages sent to the deployer by other systems
+struct DeployInbound {
+    deployer: ActorRef<DeployerMsg>,
+}
+
+impl ActorFactoryArgs<ActorRef<DeployerMsg>> for DeployInbound {
+    fn create_args(deployer: ActorRef<DeployerMsg>) -> Self {
+        Self { deployer }
+    }
+}
+
+impl Actor for DeployInbound {
+    type Msg = DeployMsg;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.deployer.tell(DeployerMsg::Received(msg), None);
+    }
+}
+
+/// The parent of an actor deployed on this system.
+///
+/// The deployed actor is stopped when it fails, and its failure is
+/// reported to the system that deployed it.
+struct DeploySupervisor {
+    factory: Factory,
+    deployment: Deployment,
+    deployer: ActorRef<DeployerMsg>,
+    actor: Option<BasicActorRef>,
+    failed: Arc<AtomicBool>,
+}
+
+type DeploySupervisorArgs = (Factory, Deployment, ActorRef<DeployerMsg>);
+
+impl ActorFactoryArgs<DeploySupervisorArgs> for DeploySupervisor {
+    fn create_args((factory, deployment, deployer): DeploySupervisorArgs) -> Self {
+        Self {
+            factory,
+            deployment,
+            deployer,
+            actor: None,
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl DeploySupervisor {
+    fn terminated(&self, ctx: &Context<SerializedMessage>) {
+        self.deployer.tell(
+            DeployerMsg::Terminated {
+                origin: self.deployment.origin,
+                id: self.deployment.id.clone(),
+                failed: self.failed.load(Ordering::Relaxed),
+            },
+            None,
+        );
+        ctx.stop(ctx.myself());
+    }
+}
+
+impl Actor for DeploySupervisor {
+    type Msg = SerializedMessage;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let d = &self.deployment;
+        let parent = ctx.myself().into();
+        match (self.factory)(&ctx.system, &parent, &d.name, &d.args, &self.failed) {
+            Ok(actor) => self.actor = Some(actor),
+            Err(e) => {
+                warn!(ctx.system.log(), "{}", e);
+                self.terminated(ctx);
+            }
+        }
+    }
+
+    fn supervisor_strategy(&self) -> Strategy {
+        Strategy::Stop
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        let Some(ref actor) = self.actor else {
+            return;
+        };
+
+        match ctx.system.serialization().deserialize_any(&msg) {
+            Ok(any) => {
+                let mut any = AnyMessage {
+                    one_time: true,
+                    msg: Some(any),
+                };
+                if actor.try_tell_any(&mut any, None).is_err() {
+                    dead_letter(&ctx.system, msg.manifest, actor.clone());
+                }
+            }
+            Err(e) => {
+                warn!(ctx.system.log(), "Failed to receive remote message: {}", e);
+                dead_letter(&ctx.system, msg.manifest, actor.clone());
+            }
+        }
+    }
+
+    // The deployed actor is a child of the supervisor, so the supervisor
+    // receives ActorTerminated when it stops, including after it failed
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            if self.actor.as_ref() == Some(&terminated.actor) {
+                self.terminated(ctx);
+            }
+        }
+    }
+}
+
+/// An actor deployed on this system, which records whether it failed
+/// for its supervisor to report.
+///
+/// An actor fails when one of its handlers panics, including the future
+/// handling a message of an async actor.
+struct Deployed<A> {
+    actor: A,
+    failed: Arc<AtomicBool>,
+}
+
+impl<A: Actor> Deployed<A> {
+    /// Run a handler of the actor, recording a panic as a failure
+    fn fail_on_panic<R>(failed: &AtomicBool, handler: impl FnOnce() -> R) -> R {
+        catch_unwind(AssertUnwindSafe(handler)).unwrap_or_else(|panic| {
+            failed.store(true, Ordering::Relaxed);
+            resume_unwind(panic)
+        })
+    }
+}
+
+impl<A: Actor> Actor for Deployed<A> {
+    type Msg = A::Msg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.actor.pre_start(ctx);
+    }
+
+    fn post_start(&mut self, ctx: &Context<Self::Msg>) {
+        let actor = &mut self.actor;
+        let failed = &self.failed;
+        Self::fail_on_panic(failed, || actor.post_start(ctx));
+    }
+
+    fn post_stop(&mut self) {
+        self.actor.post_stop();
+    }
+
+    fn supervisor_strategy(&self) -> Strategy {
+        self.actor.supervisor_strategy()
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        let actor = &mut self.actor;
+        Self::fail_on_panic(&self.failed, || actor.recv(ctx, msg, sender));
+    }
+
+    fn recv_future<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> Option<BoxFuture<'a, ()>> {
+        let actor = &mut self.actor;
+        let failed = &self.failed;
+        let handler = Self::fail_on_panic(failed, move || actor.recv_future(ctx, msg, sender))?;
+
+        let failed = failed.clone();
+        let handler = AssertUnwindSafe(handler).catch_unwind().map(move |result| {
+            if let Err(panic) = result {
+                failed.store(true, Ordering::Relaxed);
+                resume_unwind(panic);
+            }
+        });
+        Some(handler.boxed())
+    }
+
+    fn sys_recv(&mut self, ctx: &Context<Self::Msg>, msg: SystemMsg, sender: Sender) {
+        let actor = &mut self.actor;
+        Self::fail_on_panic(&self.failed, || actor.sys_recv(ctx, msg, sender));
+    }
+
+    fn persistence_conf(&self) -> Option<PersistenceConf> {
+        self.actor.persistence_conf()
+    }
+
+    fn recover_snapshot(&mut self, ctx: &Context<Self::Msg>, snapshot: Snapshot) {
+        self.actor.recover_snapshot(ctx, snapshot);
+    }
+
+    fn replay_event(&mut self, ctx: &Context<Self::Msg>, evt: Self::Msg) {
+        self.actor.replay_event(ctx, evt);
+    }
+}
+
+#[derive(Clone)]
+pub struct RemoteDeploymentArgs {
+    pub deployer: ActorRef<DeployerMsg>,
+    pub target: SocketAddr,
+    pub deployment: Deployment,
+}
+
+/// The local reference of an actor deployed on another system.
+///
+/// The actor is deployed again each time the reference is started, so
+/// a restart by the parent deploys a new instance.
+pub struct RemoteDeployment<Msg> {
+    args: RemoteDeploymentArgs,
+    id: String,
+    msg: PhantomData<Msg>,
+}
+
+impl<Msg> ActorFactoryArgs<RemoteDeploymentArgs> for RemoteDeployment<Msg>
+where
+    Msg: crate::Message,
+{
+    fn create_args(args: RemoteDeploymentArgs) -> Self {
+        Self {
+            args,
+            id: String::new(),
+            msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg> Actor for RemoteDeployment<Msg>
+where
+    Msg: crate::Message,
+{
+    type Msg = Msg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.id = Uuid::new_v4().to_simple().to_string();
+
+        let deployment = Deployment {
+            id: self.id.clone(),
+            ..self.args.deployment.clone()
+        };
+        self.args.deployer.tell(
+            DeployerMsg::Deploy {
+                target: self.args.target,
+                actor: ctx.myself().into(),
+                deployment,
+            },
+            None,
+        );
+    }
+
+    fn post_stop(&mut self) {
+        self.args.deployer.tell(
+            DeployerMsg::Undeploy {
+                target: self.args.target,
+                id: self.id.clone(),
+            },
+            None,
+        );
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match ctx.system.serialization().serialize(&msg) {
+            Ok(serialized) => self.args.deployer.tell(
+                DeployerMsg::Tell {
+                    target: self.args.target,
+                    id: self.id.clone(),
+                    msg: serialized,
+                },
+                None,
+            ),
+            Err(e) => {
+                warn!(ctx.system.log(), "Failed to send remote message: {}", e);
+                dead_letter(&ctx.system, format!("{:?}", msg), ctx.myself().into());
+            }
+        }
+    }
+}
+
+/// Error type when an actor can't be deployed on another system.
+#[derive(Debug)]
+pub enum DeployError {
+    NotRegistered(String),
+    DuplicateManifest(String),
+    Args(String),
+    Create(CreateError),
+}
+
+impl std::error::Error for DeployError {}
+
+impl fmt::Display for DeployError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NotRegistered(ref actor) => f.write_str(&format!(
+                "Failed to deploy actor. Cause: No factory is registered for {}",
+                actor
+            )),
+            Self::DuplicateManifest(ref manifest) => f.write_str(&format!(
+                "Failed to register actor factory. Cause: The manifest is already registered ({})",
+                manifest
+            )),
+            Self::Args(ref err) => f.write_str(&format!(
+                "Failed to deploy actor. Cause: Invalid actor args ({})",
+                err
+            )),
+            Self::Create(ref err) => {
+                f.write_str(&format!("Failed to deploy actor. Cause: {}", err))
+            }
+        }
+    }
+}
+
+impl From<CreateError> for DeployError {
+    fn from(err: CreateError) -> Self {
+        Self::Create(err)
+    }
+}
@@ -0,0 +1,449 @@
+pub(crate) mod deploy;
+pub(crate) mod transport;
+
+pub use self::deploy::DeployError;
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt, io,
+    marker::PhantomData,
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use config::Config;
+use serde::{de::DeserializeOwned, Serialize};
+use slog::warn;
+use uuid::Uuid;
+
+use crate::actor_ref::{ActorRef, ActorRefFactory, ActorReference, BasicActorRef, Sender, Tell};
+use crate::{
+    actor::{Actor, ActorAddress, ActorArgs, ActorFactoryArgs, Context, CreateError, Props},
+    remote::{
+        deploy::{
+            Deployables, Deployer, DeployerMsg, Deployment, RemoteDeployment, RemoteDeploymentArgs,
+        },
+        transport::{dead_letter, Frame, Inbound, WireEnvelope},
+    },
+    system::{ActorSystem, SerializeError},
+    AnyMessage, Message,
+};
+
+/// Remoting lets actor systems in different processes exchange
+/// messages over TCP.
+///
+/// Each system that takes part starts remoting on a local address.
+/// The system's host becomes the remoting address, so the addresses of
+/// its actors include it. A remote actor is then messaged through an
+/// `ActorRef` returned by `actor_ref`, using the actor's full address:
+///
+/// ```ignore
+/// let remote = Remoting::start(&sys, "127.0.0.1:7000")?;
+/// let addr = "riker://other@127.0.0.1:7001/user/echo".parse()?;
+/// let echo: ActorRef<Ping> = remote.actor_ref(&addr)?;
+/// echo.tell(Ping("hello".into()), None);
+/// ```
+///
+/// Messages are serialized with the system's `Serialization` registry,
+/// so the message type must be registered, with the same manifest,
+/// on both systems. The remote actor's message type must be the type
+/// that is registered. The sender of a remote message is not sent.
+///
+/// Connections are established on first use and re-established after
+/// they fail. Messages that can't be delivered are sent to dead letters:
+/// on the sending system if the remote system is unreachable, and on
+/// the receiving system if the recipient doesn't exist or doesn't accept
+/// the message type.
+///
+/// Actors can also be deployed on a remote system, see `actor_of_args_at`.
+#[derive(Clone)]
+pub struct Remoting {
+    sys: ActorSystem,
+    daemon: ActorRef<RemoteMsg>,
+    deployer: ActorRef<DeployerMsg>,
+    deployables: Deployables,
+    proxies: Proxies,
+    addr: SocketAddr,
+}
+
+/// The `ActorRef`s of remote actors by address and message type
+type Proxies = Arc<Mutex<HashMap<(String, TypeId), Box<dyn Any + Send>>>>;
+
+impl Remoting {
+    /// Start remoting for `sys`, accepting connections on `addr`.
+    pub fn start(sys: &ActorSystem, addr: impl ToSocketAddrs) -> Result<Self, RemoteError> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        sys.set_remote_host(&addr.to_string());
+
+        deploy::register(sys).map_err(RemoteError::Serialize)?;
+
+        let inbound = Arc::new(Inbound::default());
+        let daemon = sys.sys_actor_of_args::<RemoteDaemon, _>("remoting", inbound.clone())?;
+        let settings = RemoteSettings::from(&sys.config());
+        transport::listen(listener, inbound, daemon.clone(), settings, sys.clone())?;
+
+        let deployables = Deployables::default();
+        let deployer = sys
+            .sys_actor_of_args::<Deployer, _>("deployer", (daemon.clone(), deployables.clone()))?;
+
+        Ok(Self {
+            sys: sys.clone(),
+            daemon,
+            deployer,
+            deployables,
+            proxies: Proxies::default(),
+            addr,
+        })
+    }
+
+    /// Returns the address remoting accepts connections on
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop remoting.
+    ///
+    /// The listener and all connections are closed. Messages sent to
+    /// remote actor references afterwards are sent to dead letters.
+    pub fn stop(&self) {
+        self.sys.stop(&self.deployer);
+        self.sys.stop(&self.daemon);
+    }
+
+    /// Returns the actor system remoting was started for
+    #[must_use]
+    pub const fn system(&self) -> &ActorSystem {
+        &self.sys
+    }
+
+    /// Returns a reference to the actor at a full actor address,
+    /// e.g. `riker://my-app@127.0.0.1:7001/user/echo`.
+    ///
+    /// The reference is backed by a local actor that serializes the
+    /// messages it receives and sends them to the remote system.
+    /// Its `address()` is the remote actor's address. The local actor
+    /// is created once per address and message type, later calls
+    /// return the same reference until the local actor is stopped.
+    pub fn actor_ref<Msg: Message>(
+        &self,
+        address: &ActorAddress,
+    ) -> Result<ActorRef<Msg>, CreateError> {
+        let key = (address.to_string(), TypeId::of::<Msg>());
+        let mut proxies = self.proxies.lock().unwrap();
+
+        // a cached reference is used while its actor hasn't been stopped
+        if let Some(proxy) = proxies
+            .get(&key)
+            .and_then(|proxy| proxy.downcast_ref::<ActorRef<Msg>>())
+            .filter(|proxy| {
+                self.daemon
+                    .cell
+                    .is_child(&BasicActorRef::from((*proxy).clone()))
+            })
+        {
+            return Ok(proxy.clone());
+        }
+
+        let proxy = self.create_proxy(address)?;
+        proxies.insert(key, Box::new(proxy.clone()));
+        drop(proxies);
+
+        Ok(proxy)
+    }
+
+    fn create_proxy<Msg: Message>(
+        &self,
+        address: &ActorAddress,
+    ) -> Result<ActorRef<Msg>, CreateError> {
+        let addr = match address.port {
+            Some(_) => address
+                .authority()
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut a| a.next()),
+            None => None,
+        };
+        let addr = addr.ok_or_else(|| CreateError::InvalidName(address.to_string()))?;
+
+        let name = format!("remote-{}", Uuid::new_v4().to_simple());
+        let props = Props::new_args(
+            RemoteActorRef::create_args,
+            (self.daemon.clone(), addr, address.path.to_string()),
+        );
+        let proxy = self.sys.provider.create_actor(
+            props,
+            &name,
+            &BasicActorRef::from(self.daemon.clone()),
+            &self.sys,
+        )?;
+
+        Ok(ActorRef::new(proxy.cell.remote(address.clone())))
+    }
+
+    /// Register an actor that other systems can deploy on this system
+    /// with `actor_of_args_at`.
+    ///
+    /// The actor must be registered, with the same manifest, on both the
+    /// deploying system and the system it's deployed on.
+    pub fn register_deployable<A, Args>(&self, manifest: &str) -> Result<(), DeployError>
+    where
+        A: ActorFactoryArgs<Args>,
+        Args: ActorArgs + DeserializeOwned,
+    {
+        self.deployables.register::<A, Args>(manifest)
+    }
+
+    /// Create an actor on the remote system at `target` and return a
+    /// local reference to it.
+    ///
+    /// The local reference is created by `parent`, e.g. an actor's
+    /// context, with the given name. The remote system creates the actor
+    /// with its registered factory from the serialized `args`.
+    ///
+    /// The deployed actor is supervised by the local reference's parent:
+    /// when the actor fails, the parent's strategy is applied to the local
+    /// reference, and restarting it deploys a new instance. When the actor
+    /// stops, the local reference stops, so that its parent and watchers
+    /// are notified. Stopping the local reference stops the actor.
+    pub fn actor_of_args_at<A, Args>(
+        &self,
+        parent: &impl ActorRefFactory,
+        target: SocketAddr,
+        name: &str,
+        args: Args,
+    ) -> Result<ActorRef<A::Msg>, DeployError>
+    where
+        A: ActorFactoryArgs<Args>,
+        Args: ActorArgs + Serialize,
+    {
+        let args = serde_json::to_vec(&args).map_err(|e| DeployError::Args(e.to_string()))?;
+        let deployment = Deployment {
+            id: String::new(),
+            origin: self.addr,
+            manifest: self.deployables.manifest::<A>()?,
+            name: name.to_string(),
+            args,
+        };
+
+        let props = Props::new_args(
+            RemoteDeployment::<A::Msg>::create_args,
+            RemoteDeploymentArgs {
+                deployer: self.deployer.clone(),
+                target,
+                deployment,
+            },
+        );
+        Ok(parent.actor_of_props(props, name)?)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum RemoteMsg {
+    /// A message received from a remote system
+    Inbound(WireEnvelope),
+
+    /// A message to send to the remote system at the address
+    Outbound(SocketAddr, Frame),
+}
+
+/// Delivers inbound messages and owns the outbound connections
+struct RemoteDaemon {
+    inbound: Arc<Inbound>,
+    connections: HashMap<SocketAddr, mpsc::Sender<Frame>>,
+    settings: Option<RemoteSettings>,
+}
+
+impl ActorFactoryArgs<Arc<Inbound>> for RemoteDaemon {
+    fn create_args(inbound: Arc<Inbound>) -> Self {
+        Self {
+            inbound,
+            connections: HashMap::new(),
+            settings: None,
+        }
+    }
+}
+
+impl RemoteDaemon {
+    #[allow(clippy::unused_self)]
+    fn deliver(&self, ctx: &Context<RemoteMsg>, envelope: &WireEnvelope) {
+        let sys = &ctx.system;
+        let manifest = envelope.msg.manifest.clone();
+
+        let Some(recipient) = find_actor(sys, &envelope.recipient) else {
+            let msg = format!("{} for unknown actor {}", manifest, envelope.recipient);
+            dead_letter(sys, msg, ctx.myself().into());
+            return;
+        };
+
+        let msg = match sys.serialization().deserialize_any(&envelope.msg) {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!(sys.log(), "Failed to receive remote message: {}", e);
+                dead_letter(sys, manifest, recipient);
+                return;
+            }
+        };
+
+        let mut msg = AnyMessage {
+            one_time: true,
+            msg: Some(msg),
+        };
+
+        if recipient.try_tell_any(&mut msg, None).is_err() {
+            dead_letter(sys, manifest, recipient);
+        }
+    }
+
+    fn send(&mut self, ctx: &Context<RemoteMsg>, addr: SocketAddr, frame: Frame) {
+        let settings = *self
+            .settings
+            .get_or_insert_with(|| RemoteSettings::from(&ctx.system.config()));
+
+        let connection = self.connections.entry(addr).or_insert_with(|| {
+            let (tx, rx) = mpsc::channel();
+            transport::write(addr, rx, settings, ctx.system.clone());
+            tx
+        });
+
+        if let Err(mpsc::SendError(frame)) = connection.send(frame) {
+            self.connections.remove(&addr);
+            dead_letter(&ctx.system, frame.debug, frame.recipient);
+        }
+    }
+}
+
+impl Actor for RemoteDaemon {
+    type Msg = RemoteMsg;
+
+    fn post_stop(&mut self) {
+        self.inbound.stop();
+        // dropping the senders ends the connection writers
+        self.connections.clear();
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            RemoteMsg::Inbound(envelope) => self.deliver(ctx, &envelope),
+            RemoteMsg::Outbound(addr, frame) => self.send(ctx, addr, frame),
+        }
+    }
+}
+
+fn find_actor(sys: &ActorSystem, path: &str) -> Option<BasicActorRef> {
+    let mut actor = sys.user_root().parent();
+
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        let child = actor.children().find(|child| child.name() == name)?;
+        actor = child;
+    }
+
+    Some(actor)
+}
+
+/// The local actor behind the `ActorRef` of a remote actor
+struct RemoteActorRef<Msg: Message> {
+    daemon: ActorRef<RemoteMsg>,
+    addr: SocketAddr,
+    path: String,
+    msg: PhantomData<Msg>,
+}
+
+type RemoteActorRefArgs = (ActorRef<RemoteMsg>, SocketAddr, String);
+
+impl<Msg: Message> ActorFactoryArgs<RemoteActorRefArgs> for RemoteActorRef<Msg> {
+    fn create_args((daemon, addr, path): RemoteActorRefArgs) -> Self {
+        Self {
+            daemon,
+            addr,
+            path,
+            msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg: Message> Actor for RemoteActorRef<Msg> {
+    type Msg = Msg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match ctx.system.serialization().serialize(&msg) {
+            Ok(serialized) => {
+                let frame = Frame {
+                    envelope: WireEnvelope {
+                        recipient: self.path.clone(),
+                        msg: serialized,
+                    },
+                    recipient: ctx.myself().into(),
+                    debug: format!("{:?}", msg),
+                };
+
+                self.daemon
+                    .tell(RemoteMsg::Outbound(self.addr, frame), None);
+            }
+            Err(e) => {
+                warn!(ctx.system.log(), "Failed to send remote message: {}", e);
+                dead_letter(&ctx.system, format!("{:?}", msg), ctx.myself().into());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RemoteSettings {
+    pub reconnect_interval: Duration,
+    pub reconnect_attempts: u32,
+    pub max_frame_bytes: usize,
+}
+
+impl<'a> From<&'a Config> for RemoteSettings {
+    fn from(config: &Config) -> Self {
+        Self {
+            reconnect_interval: Duration::from_millis(
+                config
+                    .get::<u64>("remote.reconnect_interval_millis")
+                    .unwrap(),
+            ),
+            reconnect_attempts: config.get::<u32>("remote.reconnect_attempts").unwrap(),
+            max_frame_bytes: config.get::<usize>("remote.max_frame_bytes").unwrap(),
+        }
+    }
+}
+
+/// Error type when remoting fails to start.
+#[derive(Debug)]
+pub enum RemoteError {
+    Io(io::Error),
+    Serialize(SerializeError),
+    Create(CreateError),
+}
+
+impl std::error::Error for RemoteError {}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Io(ref err) => f.write_str(&format!("Failed to start remoting. Cause: {}", err)),
+            Self::Serialize(ref err) => {
+                f.write_str(&format!("Failed to start remoting. Cause: {}", err))
+            }
+            Self::Create(ref err) => {
+                f.write_str(&format!("Failed to start remoting. Cause: {}", err))
+            }
+        }
+    }
+}
+
+impl From<io::Error> for RemoteError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<CreateError> for RemoteError {
+    fn from(err: CreateError) -> Self {
+        Self::Create(err)
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use slog::{debug, info, warn};
+
+use crate::actor_ref::{ActorRef, BasicActorRef, Tell};
+use crate::{
+    actor::{DeadLetter, Publish},
+    remote::{RemoteMsg, RemoteSettings},
+    system::{ActorSystem, SerializedMessage},
+};
+
+/// A message as it is sent over the network
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WireEnvelope {
+    pub recipient: String,
+    pub msg: SerializedMessage,
+}
+
+/// A message waiting to be written to a connection
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub envelope: WireEnvelope,
+    /// The local reference of the remote recipient, used for dead letters
+    pub recipient: BasicActorRef,
+    pub debug: String,
+}
+
+/// The inbound side of remoting: the listener and accepted connections.
+///
+/// `running` is cleared and the accepted connections are shut down
+/// when remoting stops, which ends the listener and reader threads.
+#[derive(Default)]
+pub struct Inbound {
+    pub running: AtomicBool,
+    pub streams: Mutex<Vec<TcpStream>>,
+}
+
+impl Inbound {
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        for stream in self.streams.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Serialize a frame, failing if it's larger than `max_frame_bytes`
+fn encode_frame(envelope: &WireEnvelope, max_frame_bytes: usize) -> io::Result<Vec<u8>> {
+    let bytes = serde_json::to_vec(envelope)?;
+    if bytes.len() > max_frame_bytes {
+        return Err(frame_too_large(bytes.len(), max_frame_bytes));
+    }
+
+    Ok(bytes)
+}
+
+fn write_frame(stream: &mut TcpStream, bytes: &[u8]) -> io::Result<()> {
+    #[allow(clippy::cast_possible_truncation)]
+    let len = bytes.len() as u32;
+
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(bytes)?;
+    stream.flush()
+}
+
+/// Read a frame. The length is checked before the frame is read, so
+/// that a peer can't make the system allocate more than `max_frame_bytes`.
+fn read_frame(stream: &mut TcpStream, max_frame_bytes: usize) -> io::Result<WireEnvelope> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_frame_bytes {
+        return Err(frame_too_large(len, max_frame_bytes));
+    }
+
+    let mut bytes = vec![0; len];
+    stream.read_exact(&mut bytes)?;
+
+    serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn frame_too_large(len: usize, max_frame_bytes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Frame of {} bytes exceeds remote.max_frame_bytes ({})",
+            len, max_frame_bytes
+        ),
+    )
+}
+
+pub fn dead_letter(sys: &ActorSystem, msg: String, recipient: BasicActorRef) {
+    let dl = DeadLetter {
+        msg,
+        sender: None,
+        recipient,
+    };
+
+    sys.dead_letters().tell(
+        Publish {
+            topic: "dead_letter".into(),
+            msg: dl,
+        },
+        None,
+    );
+}
+
+/// Accept connections and pass the messages received on them to the
+/// remoting daemon
+pub fn listen(
+    listener: TcpListener,
+    inbound: Arc<Inbound>,
+    daemon: ActorRef<RemoteMsg>,
+    settings: RemoteSettings,
+    sys: ActorSystem,
+) -> io::Result<()> {
+    // the listener is polled so that it can stop with the system
+    listener.set_nonblocking(true)?;
+    inbound.running.store(true, Ordering::Relaxed);
+
+    thread::spawn(move || {
+        while inbound.running.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, addr)) => {
+                    debug!(sys.log(), "Remote connection accepted from {}", addr);
+                    let accepted = accept(stream, &inbound, daemon.clone(), settings, &sys);
+                    if let Err(e) = accepted {
+                        warn!(sys.log(), "Failed to accept remote connection: {}", e);
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => {
+                    warn!(sys.log(), "Remote listener failed: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn accept(
+    stream: TcpStream,
+    inbound: &Inbound,
+    daemon: ActorRef<RemoteMsg>,
+    settings: RemoteSettings,
+    sys: &ActorSystem,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    inbound.streams.lock().unwrap().push(stream.try_clone()?);
+
+    let mut stream = stream;
+    let sys = sys.clone();
+    thread::spawn(move || loop {
+        match read_frame(&mut stream, settings.max_frame_bytes) {
+            Ok(envelope) => daemon.tell(RemoteMsg::Inbound(envelope), None),
+            // the connection ends when the remote system disconnects
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                // an invalid or oversized frame closes the connection
+                warn!(sys.log(), "Closing remote connection: {}", e);
+                let _ = stream.shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// The connection to a remote system, shared by its writer and the
+/// thread that reconnects to it while it's unreachable
+#[derive(Default)]
+struct Connection {
+    stream: Mutex<Option<TcpStream>>,
+    unreachable: AtomicBool,
+    closed: AtomicBool,
+}
+
+fn connect(addr: SocketAddr) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/// Write the frames queued for the actor system at `addr`.
+///
+/// The connection is established when the first frame is written and
+/// is re-established after it fails. A frame that can't be written after
+/// `remote.reconnect_attempts` attempts is sent to dead letters, as is
+/// a frame larger than `remote.max_frame_bytes`. The remote system is
+/// then unreachable: the frames queued for it are sent to dead letters
+/// at once, until reconnecting in the background succeeds. The writer
+/// ends when the queue's sender is dropped.
+pub fn write(
+    addr: SocketAddr,
+    frames: Receiver<Frame>,
+    settings: RemoteSettings,
+    sys: ActorSystem,
+) {
+    thread::spawn(move || {
+        let conn = Arc::new(Connection::default());
+
+        for frame in frames {
+            if conn.unreachable.load(Ordering::Relaxed) {
+                dead_letter(&sys, frame.debug, frame.recipient);
+                continue;
+            }
+
+            let bytes = match encode_frame(&frame.envelope, settings.max_frame_bytes) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!(sys.log(), "Failed to send remote message: {}", e);
+                    dead_letter(&sys, frame.debug, frame.recipient);
+                    continue;
+                }
+            };
+
+            let mut attempts = 0;
+            let mut stream = conn.stream.lock().unwrap();
+
+            loop {
+                if stream.is_none() {
+                    match connect(addr) {
+                        Ok(s) => *stream = Some(s),
+                        Err(e) => debug!(sys.log(), "Failed to connect to {}: {}", addr, e),
+                    }
+                }
+
+                if let Some(ref mut s) = *stream {
+                    if write_frame(s, &bytes).is_ok() {
+                        break;
+                    }
+                    *stream = None;
+                }
+
+                attempts += 1;
+                if attempts > settings.reconnect_attempts {
+                    warn!(sys.log(), "Remote system {} is unreachable", addr);
+                    dead_letter(&sys, frame.debug, frame.recipient);
+                    conn.unreachable.store(true, Ordering::Relaxed);
+                    drop(stream);
+                    reconnect(addr, conn.clone(), settings, sys.clone());
+                    break;
+                }
+
+                thread::sleep(settings.reconnect_interval);
+            }
+        }
+
+        conn.closed.store(true, Ordering::Relaxed);
+    });
+}
+
+/// Reconnect to the unreachable system at `addr` every
+/// `remote.reconnect_interval`, until it succeeds or the writer ends
+fn reconnect(addr: SocketAddr, conn: Arc<Connection>, settings: RemoteSettings, sys: ActorSystem) {
+    thread::spawn(move || {
+        while !conn.closed.load(Ordering::Relaxed) {
+            thread::sleep(settings.reconnect_interval);
+
+            if let Ok(stream) = connect(addr) {
+                *conn.stream.lock().unwrap() = Some(stream);
+                conn.unreachable.store(false, Ordering::Relaxed);
+                info!(sys.log(), "Remote system {} is reachable again", addr);
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,190 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    actor::{Actor, ActorFactoryArgs, ActorPath, Context, Subscribe, SysTopic},
+    actor_ref::{ActorReference, BoxedTell, Sender, Tell},
+    system::{SystemEvent, SystemMsg},
+    Message,
+};
+
+/// An actor subscribed to events of a type. `tell` is its `BoxedTell`
+/// of the event type.
+struct Subscription {
+    path: ActorPath,
+    tell: Box<dyn Any + Send>,
+}
+
+/// Sends an event of one type to the subscribers of a group type
+type Upcast = Arc<dyn Fn(&EventBus, &dyn Any, &Sender) + Send + Sync>;
+
+#[derive(Default)]
+struct Registry {
+    subs: HashMap<TypeId, Vec<Subscription>>,
+    /// The groups of each event type, by the group type
+    groups: HashMap<TypeId, HashMap<TypeId, Upcast>>,
+}
+
+/// A system wide stream of events, subscribed to by the type of the events.
+///
+/// Unlike a `Channel`, which carries a single message type, events of any
+/// type can be published to the stream and are sent to the actors
+/// subscribed to their type:
+///
+/// ```ignore
+/// sys.event_stream().subscribe::<OrderPlaced>(ctx.myself());
+/// sys.event_stream().publish(OrderPlaced { id: 1 });
+/// ```
+///
+/// Event types can be grouped by registering a group type that can be
+/// created from each of them, such as an enum of the events. Subscribers
+/// to the group type are sent each event of the group, converted to it.
+/// Groups are not nested: an event converted to a group type is not sent
+/// to the groups of the group type.
+///
+/// Actors are unsubscribed from the stream when they terminate.
+///
+/// The stream of an actor system is available at
+/// `ActorSystem::event_stream`.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl EventBus {
+    /// Subscribe an actor to the events of type `T`
+    ///
+    /// Subscribing an actor that's already subscribed to `T` has no effect.
+    pub fn subscribe<T: Message>(&self, actor: impl Tell<T>) {
+        let mut registry = self.registry.lock().unwrap();
+        let subs = registry.subs.entry(TypeId::of::<T>()).or_default();
+        if !subs.iter().any(|sub| &sub.path == actor.path()) {
+            subs.push(Subscription {
+                path: actor.path().clone(),
+                tell: Box::new(Box::new(actor) as BoxedTell<T>),
+            });
+        }
+        drop(registry);
+    }
+
+    /// Unsubscribe an actor from the events of type `T`
+    pub fn unsubscribe<T: Message>(&self, actor: &dyn ActorReference) {
+        let mut registry = self.registry.lock().unwrap();
+        if let Some(subs) = registry.subs.get_mut(&TypeId::of::<T>()) {
+            subs.retain(|sub| &sub.path != actor.path());
+        }
+        registry.subs.retain(|_, subs| !subs.is_empty());
+    }
+
+    /// Unsubscribe an actor from the events of every type
+    pub fn unsubscribe_all(&self, actor: &dyn ActorReference) {
+        let mut registry = self.registry.lock().unwrap();
+        for subs in registry.subs.values_mut() {
+            subs.retain(|sub| &sub.path != actor.path());
+        }
+        registry.subs.retain(|_, subs| !subs.is_empty());
+    }
+
+    /// Register `G` as a group of events that includes the events of type `T`
+    ///
+    /// Each event of type `T` published is then also sent as a `G` to the
+    /// actors subscribed to `G`. Registering the same group again has no
+    /// effect.
+    pub fn register_group<T, G>(&self)
+    where
+        T: Message,
+        G: Message + From<T>,
+    {
+        let upcast: Upcast = Arc::new(|bus, evt, sender| {
+            if let Some(evt) = evt.downcast_ref::<T>() {
+                bus.deliver(G::from(evt.clone()), sender.clone());
+            }
+        });
+
+        let mut registry = self.registry.lock().unwrap();
+        registry
+            .groups
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .insert(TypeId::of::<G>(), upcast);
+    }
+
+    /// Publish an event to the actors subscribed to its type and to the
+    /// groups it's registered with
+    pub fn publish<T: Message>(&self, evt: T) {
+        self.publish_with_sender(evt, None);
+    }
+
+    /// Publish an event with the sender the subscribers receive it from
+    pub fn publish_with_sender<T: Message>(&self, evt: T, sender: Sender) {
+        let groups: Vec<Upcast> = self
+            .registry
+            .lock()
+            .unwrap()
+            .groups
+            .get(&TypeId::of::<T>())
+            .map(|groups| groups.values().cloned().collect())
+            .unwrap_or_default();
+
+        for upcast in groups {
+            upcast(self, &evt, &sender);
+        }
+
+        self.deliver(evt, sender);
+    }
+
+    /// Send an event to the actors subscribed to its type
+    fn deliver<T: Message>(&self, evt: T, sender: Sender) {
+        let registry = self.registry.lock().unwrap();
+        let subs: Vec<BoxedTell<T>> = registry
+            .subs
+            .get(&TypeId::of::<T>())
+            .into_iter()
+            .flatten()
+            .filter_map(|sub| sub.tell.downcast_ref::<BoxedTell<T>>())
+            .map(Tell::box_clone)
+            .collect();
+        drop(registry);
+
+        if let Some((last, subs)) = subs.split_last() {
+            for sub in subs {
+                sub.tell(evt.clone(), sender.clone());
+            }
+            last.tell(evt, sender);
+        }
+    }
+}
+
+/// Unsubscribes actors from the event stream when they terminate
+pub struct EventBusWatcher {
+    bus: EventBus,
+}
+
+impl ActorFactoryArgs<EventBus> for EventBusWatcher {
+    fn create_args(bus: EventBus) -> Self {
+        Self { bus }
+    }
+}
+
+impl Actor for EventBusWatcher {
+    type Msg = SystemEvent;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let sub = Subscribe {
+            topic: SysTopic::ActorTerminated.into(),
+            actor: Box::new(ctx.myself()),
+        };
+        ctx.system.sys_events().tell(sub, None);
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {}
+
+    fn sys_recv(&mut self, _ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            self.bus.unsubscribe_all(&terminated.actor);
+        }
+    }
+}
@@ -1,13 +1,24 @@
+pub(crate) mod event_bus;
 pub(crate) mod logger;
+pub(crate) mod persist;
+pub(crate) mod serialization;
 pub(crate) mod timer;
 
 // Public riker::system API (plus the pub data types in this file)
+pub use self::event_bus::EventBus;
+pub use self::persist::{
+    EventEnvelope, EventQuery, EventStore, EventStream, Evt, FsSnapshotStore, MemoryEventStore,
+    PersistError, PersistenceConf, Retention, Snapshot, SnapshotMeta, SnapshotStore,
+};
+#[cfg(feature = "binary")]
+pub use self::serialization::BinaryCodec;
+pub use self::serialization::{Codec, JsonCodec, Serialization, SerializeError, SerializedMessage};
 pub use self::timer::{BasicTimer, Timer};
 
 use std::{
     fmt,
     ops::Deref,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, RwLock},
     time::{Duration, Instant},
 };
 
@@ -30,11 +41,14 @@ use crate::{
     actor::{props::ActorFactory, *},
     kernel::provider::{create_root, Provider},
     load_config,
+    system::event_bus::EventBusWatcher,
     system::logger::*,
+    system::persist::PersistStores,
     system::timer::*,
     validate::{validate_name, InvalidPath},
     AnyMessage, Message,
 };
+use serde::{de::DeserializeOwned, Serialize};
 use slog::{debug, Logger};
 
 #[derive(Clone, Debug)]
@@ -168,6 +182,8 @@ pub struct ProtoSystem {
     id: Uuid,
     name: String,
     pub host: Arc<String>,
+    /// The remoting address, once remoting has started
+    remote_host: RwLock<Option<Arc<String>>>,
     config: Config,
     pub(crate) sys_settings: SystemSettings,
     started_at: DateTime<Utc>,
@@ -246,6 +262,9 @@ pub struct ActorSystem {
     pub timer: TimerRef,
     pub sys_channels: Option<SysChannels>,
     pub(crate) provider: Provider,
+    persist: PersistStores,
+    serialization: Serialization,
+    event_bus: EventBus,
 }
 
 impl fmt::Display for ActorSystem {
@@ -308,6 +327,7 @@ impl ActorSystem {
             id: Uuid::new_v4(),
             name: name.to_string(),
             host: Arc::new("localhost".to_string()),
+            remote_host: RwLock::new(None),
             config: cfg.clone(),
             sys_settings: SystemSettings::from(cfg),
             started_at: Utc::now(),
@@ -324,6 +344,9 @@ impl ActorSystem {
             sys_channels: None,
             sys_actors: None,
             provider: prov.clone(),
+            persist: PersistStores::default(),
+            serialization: Serialization::default(),
+            event_bus: EventBus::default(),
         };
 
         // 3. create initial actor hierarchy
@@ -337,6 +360,10 @@ impl ActorSystem {
         let props = DeadLetterLogger::props(sys.dead_letters(), sys.log());
         let _dl_logger = sys_actor_of_props(&prov, &sys, props, "dl_logger")?;
 
+        // 6. start event stream watcher
+        let props = Props::new_args(EventBusWatcher::create_args, sys.event_bus.clone());
+        let _watcher = sys_actor_of_props(&prov, &sys, props, "event_stream")?;
+
         sys.complete_start();
 
         debug!(sys.log, "Actor system [{}] [{}] started", sys.id(), name);
@@ -362,13 +389,22 @@ impl ActorSystem {
             .num_seconds() as u64
     }
 
-    /// Returns the hostname used when the system started
-    ///
-    /// The host is used in actor addressing.
+    /// Returns the host of the system
     ///
-    /// Currently not used, but will be once system clustering is introduced.
+    /// The host is used in actor addressing. It is the hostname used
+    /// when the system started unless remoting is started, in which
+    /// case it's the remoting address.
     pub fn host(&self) -> Arc<String> {
-        self.proto.host.clone()
+        self.proto
+            .remote_host
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.proto.host.clone())
+    }
+
+    pub(crate) fn set_remote_host(&self, host: &str) {
+        *self.proto.remote_host.write().unwrap() = Some(Arc::new(host.to_string()));
     }
 
     /// Returns the UUID assigned to the system
@@ -450,6 +486,69 @@ impl ActorSystem {
         self.sys_events().tell(Publish { topic, msg: evt }, None);
     }
 
+    /// Register the event store used by persistent actors
+    /// with a message type of `Msg`
+    pub fn set_event_store<Msg: Message>(&self, store: impl EventStore<Msg>) {
+        self.persist.set_event_store::<Msg>(Arc::new(store));
+    }
+
+    /// Returns the event store registered for `Msg`
+    #[must_use]
+    pub fn event_store<Msg: Message>(&self) -> Option<Arc<dyn EventStore<Msg>>> {
+        self.persist.event_store::<Msg>()
+    }
+
+    /// Register the snapshot store used by all persistent actors
+    pub fn set_snapshot_store(&self, store: impl SnapshotStore) {
+        self.persist.set_snapshot_store(Arc::new(store));
+    }
+
+    /// Returns the registered snapshot store
+    #[must_use]
+    pub fn snapshot_store(&self) -> Option<Arc<dyn SnapshotStore>> {
+        self.persist.snapshot_store()
+    }
+
+    /// Returns the registry of serializable message types
+    #[must_use]
+    pub const fn serialization(&self) -> &Serialization {
+        &self.serialization
+    }
+
+    /// Register a message type exchanged internally, e.g. by remoting or
+    /// the cluster, as JSON. Registering it again, e.g. when remoting is
+    /// started again, is not an error.
+    pub(crate) fn register_internal<T>(&self, manifest: &str) -> Result<(), SerializeError>
+    where
+        T: Message + Serialize + DeserializeOwned,
+    {
+        match self.serialization.register::<T, _>(manifest, JsonCodec) {
+            Ok(()) | Err(SerializeError::DuplicateManifest(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the stream of events subscribed to by type
+    #[must_use]
+    pub const fn event_stream(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Returns the path of a full actor address of this system.
+    ///
+    /// Fails if the address is invalid or of another actor system.
+    pub(crate) fn local_path(&self, address: &str) -> Result<String, InvalidPath> {
+        let addr = address
+            .parse::<ActorAddress>()
+            .map_err(|_| InvalidPath::new(address))?;
+
+        if addr.system == self.name() && addr.authority() == *self.host() {
+            Ok(addr.path.to_string())
+        } else {
+            Err(InvalidPath::new(address))
+        }
+    }
+
     /// Returns the `Config` used by the system
     pub fn config(&self) -> Config {
         self.proto.config.clone()
@@ -628,6 +727,10 @@ impl TmpActorRefFactory for ActorSystem {
 
 impl ActorSelectionFactory for ActorSystem {
     fn select(&self, path: &str) -> Result<ActorSelection, InvalidPath> {
+        if path.starts_with("riker://") {
+            return self.select(&self.local_path(path)?);
+        }
+
         let anchor = self.user_root();
         let (anchor, path_str) = if path.starts_with('/') {
             let anchor_path = format!("{}/", anchor.path().deref().clone());
@@ -818,11 +921,19 @@ fn sys_channels(prov: &Provider, sys: &ActorSystem) -> Result<SysChannels, Syste
     let sys_events = sys_actor_of::<EventsChannel>(prov, sys, "sys_events")?;
     let dead_letters = sys_actor_of::<Channel<DeadLetter>>(prov, sys, "dead_letters")?;
 
-    // subscribe the dead_letters channel to actor terminated events
+    // subscribe the system channels to actor terminated events
     // so that any future subscribed actors that terminate are automatically
-    // unsubscribed from the dead_letters channel
-    // let msg = ChannelMsg::Subscribe(SysTopic::ActorTerminated.into(), dl.clone());
-    // es.tell(msg, None);
+    // unsubscribed from the channels
+    for channel in &[
+        BasicActorRef::from(sys_events.clone()),
+        dead_letters.clone().into(),
+    ] {
+        let sub = Subscribe {
+            topic: SysTopic::ActorTerminated.into(),
+            actor: Box::new(channel.clone()),
+        };
+        sys_events.tell(sub, None);
+    }
 
     Ok(SysChannels {
         sys_events,
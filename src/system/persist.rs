@@ -1,155 +1,594 @@
-use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
-use chrono::prelude::{DateTime, Utc};
+use chrono::prelude::{DateTime, TimeZone, Utc};
 use config::Config;
-use log::warn;
+use futures::channel::mpsc;
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
 
-use crate::actors::{Actor, ActorRef, BoxActor, BoxActorProd, Context};
-use crate::actors::{ActorRefFactory, Props, SysTell, Tell, TmpActorRefFactory};
-use crate::protocol::{ActorMsg, ESMsg, Message, SystemMsg};
+use crate::actor_ref::{ActorRefFactory, Sender, TmpActorRefFactory};
+use crate::{
+    actor::{Actor, ActorFactoryArgs, Context},
+    system::{ActorSystem, Timer},
+    validate::validate_name,
+    Message,
+};
 
-pub struct EsManager<Evs: EventStore> {
-    es: Evs,
+/// Persistence configuration of a persistent actor.
+///
+/// Returned by `Actor::persistence_conf`. The combination of `id` and
+/// `keyspace` identifies the events and snapshots that belong to the actor.
+#[derive(Clone, Debug)]
+pub struct PersistenceConf {
+    pub id: String,
+    pub keyspace: String,
+    pub retention: Retention,
+}
+
+impl PersistenceConf {
+    #[must_use]
+    pub fn new(id: &str, keyspace: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            keyspace: keyspace.to_string(),
+            retention: Retention::default(),
+        }
+    }
+}
+
+/// Retention policy applied each time a snapshot is saved.
+///
+/// By default all snapshots and events are kept.
+#[derive(Clone, Debug, Default)]
+pub struct Retention {
+    /// Number of most recent snapshots to keep. `None` keeps all snapshots.
+    pub keep_snapshots: Option<usize>,
+
+    /// Delete the events covered by the oldest snapshot that is kept.
+    ///
+    /// Applies on its own as well: with `keep_snapshots` set to `None`
+    /// the events covered by the oldest saved snapshot are deleted.
+    pub delete_events: bool,
+}
+
+/// A persisted event and its sequence number.
+///
+/// Sequence numbers are assigned per persistent actor, starting at 1.
+#[derive(Clone, Debug)]
+pub struct Evt<Msg: Message> {
+    pub seq_nr: u64,
+    pub date: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub msg: Msg,
+}
+
+impl<Msg: Message> Evt<Msg> {
+    pub fn new(seq_nr: u64, msg: Msg) -> Self {
+        Self {
+            seq_nr,
+            date: Utc::now(),
+            tags: Vec::new(),
+            msg,
+        }
+    }
 }
 
-impl<Evs: EventStore> EsManager<Evs> {
-    fn new(es: Evs) -> BoxActor<Evs::Msg> {
-        let actor: EsManager<Evs> = EsManager { es: es };
+/// A persisted event as returned by event queries.
+///
+/// `offset` orders all events of an event store. Queries by
+/// persistence id use the event's sequence number as the offset.
+#[derive(Clone, Debug)]
+pub struct EventEnvelope<Msg: Message> {
+    pub offset: u64,
+    pub id: String,
+    pub keyspace: String,
+    pub evt: Evt<Msg>,
+}
 
-        Box::new(actor)
+/// Storage for the events of persistent actors.
+///
+/// Implement this trait to support a specific storage or database.
+/// An event store handles the events of a single message type and is
+/// registered with `ActorSystem::set_event_store`.
+pub trait EventStore<Msg: Message>: Send + Sync + 'static {
+    fn insert(&self, id: &str, keyspace: &str, evt: Evt<Msg>) -> Result<(), PersistError>;
+
+    fn load(&self, id: &str, keyspace: &str) -> Vec<Evt<Msg>>;
+
+    /// Load the events with a sequence number of at least `from_seq_nr`.
+    fn load_from(&self, id: &str, keyspace: &str, from_seq_nr: u64) -> Vec<Evt<Msg>> {
+        self.load(id, keyspace)
+            .into_iter()
+            .filter(|evt| evt.seq_nr >= from_seq_nr)
+            .collect()
     }
 
-    pub fn props(config: &Config) -> BoxActorProd<Evs::Msg> {
-        let es = Evs::new(config);
-        Props::new_args(EsManager::new, es)
+    /// Load the events tagged with `tag` that have an offset of at
+    /// least `from_offset`, ordered by offset.
+    ///
+    /// Stores that don't support tags return no events.
+    fn load_by_tag(&self, _tag: &str, _from_offset: u64) -> Vec<EventEnvelope<Msg>> {
+        Vec::new()
     }
+
+    /// Delete the events with a sequence number up to and including `to_seq_nr`.
+    fn delete_to(&self, _id: &str, _keyspace: &str, _to_seq_nr: u64) {}
 }
 
-impl<Evs: EventStore> Actor for EsManager<Evs> {
-    type Msg = Evs::Msg;
+struct MemoryEvents<Msg: Message> {
+    offset: u64,
+    events: HashMap<(String, String), Vec<EventEnvelope<Msg>>>,
+}
 
-    fn other_receive(
-        &mut self,
-        _: &Context<Self::Msg>,
-        msg: ActorMsg<Self::Msg>,
-        sender: Option<ActorRef<Self::Msg>>,
-    ) {
-        if let ActorMsg::ES(msg) = msg {
-            match msg {
-                ESMsg::Persist(evt, id, keyspace, og_sender) => {
-                    self.es.insert(&id, &keyspace, evt.clone());
-                    sender
-                        .unwrap()
-                        .sys_tell(SystemMsg::Persisted(evt.msg, og_sender), None);
-                }
-                ESMsg::Load(id, keyspace) => {
-                    let result = self.es.load(&id, &keyspace);
-                    sender.unwrap().tell(ESMsg::LoadResult(result), None);
-                }
-                _ => {}
-            }
+/// An in-memory `EventStore`. Events are lost when the process exits.
+pub struct MemoryEventStore<Msg: Message> {
+    inner: Mutex<MemoryEvents<Msg>>,
+}
+
+impl<Msg: Message> Default for MemoryEventStore<Msg> {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(MemoryEvents {
+                offset: 0,
+                events: HashMap::new(),
+            }),
         }
     }
-
-    fn receive(&mut self, _: &Context<Self::Msg>, _: Self::Msg, _: Option<ActorRef<Self::Msg>>) {}
 }
 
-pub trait EventStore: Clone + Send + Sync + 'static {
-    type Msg: Message;
+impl<Msg: Message> EventStore<Msg> for MemoryEventStore<Msg> {
+    fn insert(&self, id: &str, keyspace: &str, evt: Evt<Msg>) -> Result<(), PersistError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.offset += 1;
 
-    fn new(config: &Config) -> Self;
+        let envelope = EventEnvelope {
+            offset: inner.offset,
+            id: id.to_string(),
+            keyspace: keyspace.to_string(),
+            evt,
+        };
 
-    fn insert(&mut self, id: &String, keyspace: &String, evt: Evt<Self::Msg>);
+        inner
+            .events
+            .entry((keyspace.to_string(), id.to_string()))
+            .or_default()
+            .push(envelope);
+        drop(inner);
+
+        Ok(())
+    }
+
+    fn load(&self, id: &str, keyspace: &str) -> Vec<Evt<Msg>> {
+        self.inner
+            .lock()
+            .unwrap()
+            .events
+            .get(&(keyspace.to_string(), id.to_string()))
+            .map(|evts| evts.iter().map(|e| e.evt.clone()).collect())
+            .unwrap_or_default()
+    }
 
-    fn load(&self, id: &String, keyspace: &String) -> Vec<Self::Msg>;
+    fn load_by_tag(&self, tag: &str, from_offset: u64) -> Vec<EventEnvelope<Msg>> {
+        let mut evts: Vec<EventEnvelope<Msg>> = self
+            .inner
+            .lock()
+            .unwrap()
+            .events
+            .values()
+            .flatten()
+            .filter(|e| e.offset >= from_offset && e.evt.tags.iter().any(|t| t == tag))
+            .cloned()
+            .collect();
+
+        evts.sort_by_key(|e| e.offset);
+        evts
+    }
+
+    fn delete_to(&self, id: &str, keyspace: &str, to_seq_nr: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(evts) = inner
+            .events
+            .get_mut(&(keyspace.to_string(), id.to_string()))
+        {
+            evts.retain(|e| e.evt.seq_nr > to_seq_nr);
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct Evt<Msg: Message> {
+/// Describes a saved snapshot.
+///
+/// A snapshot with `seq_nr` covers all events up to and including `seq_nr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotMeta {
+    pub seq_nr: u64,
     pub date: DateTime<Utc>,
-    pub msg: Msg,
 }
 
-impl<Msg: Message> Evt<Msg> {
-    pub fn new(msg: Msg) -> Self {
-        Evt {
-            date: Utc::now(),
-            msg: msg,
-        }
+/// A serialized snapshot of a persistent actor's state.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub meta: SnapshotMeta,
+    pub data: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn new<S: Serialize>(seq_nr: u64, state: &S) -> Result<Self, PersistError> {
+        let data =
+            serde_json::to_vec(state).map_err(|e| PersistError::Serialization(e.to_string()))?;
+
+        Ok(Self {
+            meta: SnapshotMeta {
+                seq_nr,
+                date: Utc::now(),
+            },
+            data,
+        })
+    }
+
+    /// Deserialize the snapshot into the actor's state type.
+    pub fn state<S: DeserializeOwned>(&self) -> Result<S, PersistError> {
+        serde_json::from_slice(&self.data).map_err(|e| PersistError::Serialization(e.to_string()))
     }
 }
 
-#[derive(Clone)]
-pub struct NoEventStore<Msg: Message> {
-    msg: Arc<Mutex<PhantomData<Msg>>>,
+/// Storage for the snapshots of persistent actors.
+///
+/// A single snapshot store is shared by all persistent actors of a system
+/// and is registered with `ActorSystem::set_snapshot_store`.
+pub trait SnapshotStore: Send + Sync + 'static {
+    fn save(&self, id: &str, keyspace: &str, snapshot: &Snapshot) -> Result<(), PersistError>;
+
+    fn load_latest(&self, id: &str, keyspace: &str) -> Result<Option<Snapshot>, PersistError>;
+
+    /// List the saved snapshots, oldest first.
+    fn list(&self, id: &str, keyspace: &str) -> Result<Vec<SnapshotMeta>, PersistError>;
+
+    /// Delete the snapshot described by `meta`.
+    ///
+    /// Snapshots saved at the same sequence number are told apart by
+    /// their date, only the one matching both is deleted.
+    fn delete(&self, id: &str, keyspace: &str, meta: &SnapshotMeta) -> Result<(), PersistError>;
+}
+
+/// A `SnapshotStore` that saves each snapshot as a file.
+///
+/// Snapshots are stored at `<dir>/<keyspace>/<id>/<seq_nr>-<timestamp>.snap`.
+pub struct FsSnapshotStore {
+    dir: PathBuf,
 }
 
-impl<Msg: Message> EventStore for NoEventStore<Msg> {
-    type Msg = Msg;
+impl FsSnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn actor_dir(&self, id: &str, keyspace: &str) -> Result<PathBuf, PersistError> {
+        validate_name(keyspace).map_err(|e| PersistError::InvalidId(e.name))?;
+        validate_name(id).map_err(|e| PersistError::InvalidId(e.name))?;
+
+        Ok(self.dir.join(keyspace).join(id))
+    }
+
+    fn entries(dir: &Path) -> Result<Vec<(SnapshotMeta, PathBuf)>, PersistError> {
+        let read = match fs::read_dir(dir) {
+            Ok(read) => read,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
 
-    fn new(_config: &Config) -> Self {
-        NoEventStore {
-            msg: Arc::new(Mutex::new(PhantomData)),
+        let mut entries = Vec::new();
+        for entry in read {
+            let path = entry?.path();
+            if let Some(meta) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_snapshot_name)
+            {
+                entries.push((meta, path));
+            }
         }
+
+        entries.sort_by_key(|(meta, _)| (meta.seq_nr, meta.date));
+        Ok(entries)
     }
+}
+
+impl SnapshotStore for FsSnapshotStore {
+    fn save(&self, id: &str, keyspace: &str, snapshot: &Snapshot) -> Result<(), PersistError> {
+        let dir = self.actor_dir(id, keyspace)?;
+        fs::create_dir_all(&dir)?;
+
+        let name = snapshot_name(&snapshot.meta);
+
+        // write to a temporary file first so that a partially
+        // written snapshot is never loaded during recovery
+        let tmp = dir.join(format!("{}.tmp", name));
+        fs::write(&tmp, &snapshot.data)?;
+        fs::rename(&tmp, dir.join(name))?;
 
-    fn insert(&mut self, _: &String, _: &String, _: Evt<Msg>) {
-        warn!("No event store configured");
+        Ok(())
     }
 
-    fn load(&self, _: &String, _: &String) -> Vec<Msg> {
-        warn!("No event store configured");
-        vec![]
+    fn load_latest(&self, id: &str, keyspace: &str) -> Result<Option<Snapshot>, PersistError> {
+        let dir = self.actor_dir(id, keyspace)?;
+
+        match Self::entries(&dir)?.pop() {
+            Some((meta, path)) => Ok(Some(Snapshot {
+                meta,
+                data: fs::read(path)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self, id: &str, keyspace: &str) -> Result<Vec<SnapshotMeta>, PersistError> {
+        let dir = self.actor_dir(id, keyspace)?;
+        Ok(Self::entries(&dir)?
+            .into_iter()
+            .map(|(meta, _)| meta)
+            .collect())
+    }
+
+    fn delete(&self, id: &str, keyspace: &str, meta: &SnapshotMeta) -> Result<(), PersistError> {
+        let dir = self.actor_dir(id, keyspace)?;
+
+        match fs::remove_file(dir.join(snapshot_name(meta))) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => Ok(res?),
+        }
+    }
+}
+
+fn snapshot_name(meta: &SnapshotMeta) -> String {
+    format!("{:020}-{}.snap", meta.seq_nr, meta.date.timestamp_millis())
+}
+
+fn parse_snapshot_name(name: &str) -> Option<SnapshotMeta> {
+    let name = name.strip_suffix(".snap")?;
+    let mut parts = name.splitn(2, '-');
+    let seq_nr = parts.next()?.parse::<u64>().ok()?;
+    let millis = parts.next()?.parse::<i64>().ok()?;
+
+    Some(SnapshotMeta {
+        seq_nr,
+        date: Utc.timestamp_millis_opt(millis).single()?,
+    })
+}
+
+/// The event and snapshot stores registered with an `ActorSystem`.
+type AnyStores = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+#[derive(Clone, Default)]
+pub struct PersistStores {
+    events: Arc<RwLock<AnyStores>>,
+    snapshots: Arc<RwLock<Option<Arc<dyn SnapshotStore>>>>,
+}
+
+impl PersistStores {
+    pub fn set_event_store<Msg: Message>(&self, store: Arc<dyn EventStore<Msg>>) {
+        self.events
+            .write()
+            .unwrap()
+            .insert(TypeId::of::<Msg>(), Box::new(store));
+    }
+
+    pub fn event_store<Msg: Message>(&self) -> Option<Arc<dyn EventStore<Msg>>> {
+        self.events
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<Msg>())
+            .and_then(|store| store.downcast_ref::<Arc<dyn EventStore<Msg>>>())
+            .cloned()
+    }
+
+    pub fn set_snapshot_store(&self, store: Arc<dyn SnapshotStore>) {
+        *self.snapshots.write().unwrap() = Some(store);
+    }
+
+    pub fn snapshot_store(&self) -> Option<Arc<dyn SnapshotStore>> {
+        self.snapshots.read().unwrap().clone()
+    }
+}
+
+pub type EventStream<Msg> = mpsc::UnboundedReceiver<EventEnvelope<Msg>>;
+
+/// Query persisted events as a `futures::Stream`.
+///
+/// A query stream first yields the events that are already persisted,
+/// starting at the given offset, and then continues to yield events as
+/// they are persisted. The event store is polled for new events every
+/// `persistence.query_interval_millis`. Dropping the stream ends the query.
+///
+/// Projections that store the offset of the last handled event can
+/// resume after a restart by querying from the next offset.
+pub trait EventQuery {
+    /// Query the events tagged with `tag`, starting at `from_offset`.
+    fn events_by_tag<Msg: Message>(&self, tag: &str, from_offset: u64) -> EventStream<Msg>;
+
+    /// Query the events of a single persistent actor, starting at `from_seq_nr`.
+    fn events_by_id<Msg: Message>(
+        &self,
+        id: &str,
+        keyspace: &str,
+        from_seq_nr: u64,
+    ) -> EventStream<Msg>;
+}
+
+impl EventQuery for ActorSystem {
+    fn events_by_tag<Msg: Message>(&self, tag: &str, from_offset: u64) -> EventStream<Msg> {
+        start_query(self, Query::Tag(tag.to_string()), from_offset)
+    }
+
+    fn events_by_id<Msg: Message>(
+        &self,
+        id: &str,
+        keyspace: &str,
+        from_seq_nr: u64,
+    ) -> EventStream<Msg> {
+        let query = Query::Id(id.to_string(), keyspace.to_string());
+        start_query(self, query, from_seq_nr)
     }
 }
 
-struct EsQueryActor<Msg: Message> {
-    rec: ActorRef<Msg>,
+fn start_query<Msg: Message>(sys: &ActorSystem, query: Query, offset: u64) -> EventStream<Msg> {
+    let (tx, rx) = mpsc::unbounded();
+    // if the query actor fails to start the sender is dropped and the stream ends
+    let _ = sys.tmp_actor_of_args::<EventQueryActor<Msg>, _>((query, offset, tx));
+    rx
 }
 
-impl<Msg: Message> EsQueryActor<Msg> {
-    fn actor(rec: ActorRef<Msg>) -> BoxActor<Msg> {
-        let actor = EsQueryActor { rec };
-        Box::new(actor)
+#[derive(Clone, Debug)]
+enum Query {
+    Tag(String),
+    Id(String, String),
+}
+
+#[derive(Clone, Debug)]
+struct PollEvents;
+
+struct EventQueryActor<Msg: Message> {
+    query: Query,
+    offset: u64,
+    tx: mpsc::UnboundedSender<EventEnvelope<Msg>>,
+    schedule: Option<Uuid>,
+}
+
+type QueryArgs<Msg> = (Query, u64, mpsc::UnboundedSender<EventEnvelope<Msg>>);
+
+impl<Msg: Message> ActorFactoryArgs<QueryArgs<Msg>> for EventQueryActor<Msg> {
+    fn create_args((query, offset, tx): QueryArgs<Msg>) -> Self {
+        Self {
+            query,
+            offset,
+            tx,
+            schedule: None,
+        }
+    }
+}
+
+impl<Msg: Message> EventQueryActor<Msg> {
+    fn load(&self, sys: &ActorSystem) -> Vec<EventEnvelope<Msg>> {
+        let Some(store) = sys.event_store::<Msg>() else {
+            return Vec::new();
+        };
+
+        match self.query {
+            Query::Tag(ref tag) => store.load_by_tag(tag, self.offset),
+            Query::Id(ref id, ref keyspace) => store
+                .load_from(id, keyspace, self.offset)
+                .into_iter()
+                .map(|evt| EventEnvelope {
+                    offset: evt.seq_nr,
+                    id: id.clone(),
+                    keyspace: keyspace.clone(),
+                    evt,
+                })
+                .collect(),
+        }
     }
 
-    fn fulfill_query(&self, evts: Vec<Msg>) {
-        self.rec.sys_tell(SystemMsg::Replay(evts), None);
+    fn stop(&mut self, ctx: &Context<PollEvents>) {
+        if let Some(id) = self.schedule.take() {
+            ctx.cancel_schedule(id);
+        }
+        ctx.stop(&ctx.myself);
     }
 }
 
-impl<Msg: Message> Actor for EsQueryActor<Msg> {
-    type Msg = Msg;
+impl<Msg: Message> Actor for EventQueryActor<Msg> {
+    type Msg = PollEvents;
 
-    fn other_receive(&mut self, ctx: &Context<Msg>, msg: ActorMsg<Msg>, _: Option<ActorRef<Msg>>) {
-        match msg {
-            ActorMsg::ES(result) => {
-                if let ESMsg::LoadResult(events) = result {
-                    self.fulfill_query(events);
-                    ctx.stop(&ctx.myself);
-                }
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let interval = QueryConfig::from(&ctx.system.config()).interval;
+        let id = ctx.schedule(
+            Duration::from_millis(0),
+            interval,
+            ctx.myself(),
+            None,
+            PollEvents,
+        );
+        self.schedule = Some(id);
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {
+        if self.tx.is_closed() {
+            self.stop(ctx);
+            return;
+        }
+
+        for evt in self.load(&ctx.system) {
+            self.offset = evt.offset + 1;
+
+            if self.tx.unbounded_send(evt).is_err() {
+                // the stream was dropped
+                self.stop(ctx);
+                return;
             }
-            _ => {}
         }
     }
+}
+
+struct QueryConfig {
+    interval: Duration,
+}
+
+impl<'a> From<&'a Config> for QueryConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            interval: Duration::from_millis(
+                config
+                    .get::<u64>("persistence.query_interval_millis")
+                    .unwrap(),
+            ),
+        }
+    }
+}
 
-    fn receive(&mut self, _: &Context<Msg>, _: Msg, _: Option<ActorRef<Msg>>) {}
+/// Error type when persisting events or snapshots fails.
+#[derive(Debug)]
+pub enum PersistError {
+    NotPersistent,
+    NoEventStore,
+    NoSnapshotStore,
+    InvalidId(String),
+    Io(io::Error),
+    Serialization(String),
 }
 
-// type QueryFuture<Msg> = Pin<Box<dyn Future<Output=Result<Vec<Msg>, Canceled>> + Send>>;
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::NotPersistent => f.write_str(
+                "Failed to persist. Cause: Actor does not return a persistence configuration",
+            ),
+            Self::NoEventStore => f.write_str(
+                "Failed to persist. Cause: No event store registered for the message type",
+            ),
+            Self::NoSnapshotStore => {
+                f.write_str("Failed to persist. Cause: No snapshot store registered")
+            }
+            Self::InvalidId(ref id) => f.write_str(&format!(
+                "Failed to persist. Cause: Invalid persistence id or keyspace ({})",
+                id
+            )),
+            Self::Io(ref err) => f.write_str(&format!("Failed to persist. Cause: {}", err)),
+            Self::Serialization(ref err) => f.write_str(&format!(
+                "Failed to persist. Cause: Serialization failed ({})",
+                err
+            )),
+        }
+    }
+}
 
-pub fn query<Msg, Ctx>(
-    id: &String,
-    keyspace: &String,
-    es: &ActorRef<Msg>,
-    ctx: &Ctx,
-    rec: ActorRef<Msg>,
-) where
-    Msg: Message,
-    Ctx: TmpActorRefFactory<Msg = Msg>,
-{
-    let props = Props::new_args(EsQueryActor::actor, rec);
-    let actor = ctx.tmp_actor_of(props).unwrap();
-    es.tell(ESMsg::Load(id.clone(), keyspace.clone()), Some(actor));
+impl From<io::Error> for PersistError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
 }
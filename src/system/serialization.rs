@@ -0,0 +1,244 @@
+use std::{
+    any::{self, Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::Message;
+
+/// A message serialized by the `Serialization` registry.
+///
+/// `manifest` is the stable id the message type was registered with.
+/// It identifies the type and codec needed to deserialize `bytes`,
+/// including in a different process.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedMessage {
+    pub manifest: String,
+    pub bytes: Vec<u8>,
+}
+
+/// A format used to convert serde types to and from bytes.
+pub trait Codec: Send + Sync + 'static {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializeError>;
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializeError>;
+}
+
+/// Serializes messages as JSON.
+///
+/// Always available, since snapshots and cluster messages are
+/// serialized as JSON as well.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializeError> {
+        serde_json::to_vec(value).map_err(|e| SerializeError::Encode(e.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializeError> {
+        serde_json::from_slice(bytes).map_err(|e| SerializeError::Decode(e.to_string()))
+    }
+}
+
+/// Serializes messages in a compact binary format. Requires the `binary` feature.
+#[cfg(feature = "binary")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BinaryCodec;
+
+#[cfg(feature = "binary")]
+impl Codec for BinaryCodec {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializeError> {
+        bincode::serialize(value).map_err(|e| SerializeError::Encode(e.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializeError> {
+        bincode::deserialize(bytes).map_err(|e| SerializeError::Decode(e.to_string()))
+    }
+}
+
+type EncodeFn = Box<dyn Fn(&dyn Any) -> Result<Vec<u8>, SerializeError> + Send + Sync>;
+type DecodeFn = Box<dyn Fn(&[u8]) -> Result<Box<dyn Any + Send>, SerializeError> + Send + Sync>;
+
+struct Registration {
+    manifest: String,
+    encode: EncodeFn,
+    decode: DecodeFn,
+}
+
+#[derive(Default)]
+struct Registry {
+    by_type: HashMap<TypeId, Arc<Registration>>,
+    by_manifest: HashMap<String, Arc<Registration>>,
+}
+
+/// The registry of message types that can be serialized.
+///
+/// Each message type is registered with a stable id, its manifest, and
+/// the codec used to serialize it. Systems that exchange serialized
+/// messages must register the same types with the same manifests.
+///
+/// The registry of an actor system is available at
+/// `ActorSystem::serialization`.
+#[derive(Clone, Default)]
+pub struct Serialization {
+    registry: Arc<RwLock<Registry>>,
+}
+
+impl Serialization {
+    /// Register a message type with a stable id and a codec
+    pub fn register<T, C>(&self, manifest: &str, codec: C) -> Result<(), SerializeError>
+    where
+        T: Message + Serialize + DeserializeOwned,
+        C: Codec,
+    {
+        let mut registry = self.registry.write().unwrap();
+
+        if registry.by_manifest.contains_key(manifest) {
+            return Err(SerializeError::DuplicateManifest(manifest.to_string()));
+        }
+
+        if let Some(reg) = registry.by_type.get(&TypeId::of::<T>()) {
+            return Err(SerializeError::DuplicateType(
+                any::type_name::<T>().to_string(),
+                reg.manifest.clone(),
+            ));
+        }
+
+        let codec = Arc::new(codec);
+        let decoder = codec.clone();
+        let reg = Arc::new(Registration {
+            manifest: manifest.to_string(),
+            encode: Box::new(move |value| {
+                value
+                    .downcast_ref::<T>()
+                    .ok_or_else(|| SerializeError::NotRegistered(any::type_name::<T>().to_string()))
+                    .and_then(|value| codec.encode(value))
+            }),
+            decode: Box::new(move |bytes| {
+                let value: T = decoder.decode(bytes)?;
+                Ok(Box::new(value))
+            }),
+        });
+
+        registry.by_type.insert(TypeId::of::<T>(), reg.clone());
+        registry.by_manifest.insert(manifest.to_string(), reg);
+        drop(registry);
+
+        Ok(())
+    }
+
+    /// Returns the manifest a message type was registered with
+    #[must_use]
+    pub fn manifest<T: Message>(&self) -> Option<String> {
+        self.registry
+            .read()
+            .unwrap()
+            .by_type
+            .get(&TypeId::of::<T>())
+            .map(|reg| reg.manifest.clone())
+    }
+
+    pub fn serialize<T: Message>(&self, msg: &T) -> Result<SerializedMessage, SerializeError> {
+        let reg = self
+            .registry
+            .read()
+            .unwrap()
+            .by_type
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .ok_or_else(|| SerializeError::NotRegistered(any::type_name::<T>().to_string()))?;
+
+        Ok(SerializedMessage {
+            manifest: reg.manifest.clone(),
+            bytes: (reg.encode)(msg)?,
+        })
+    }
+
+    pub fn deserialize<T: Message>(&self, msg: &SerializedMessage) -> Result<T, SerializeError> {
+        self.deserialize_any(msg)?
+            .downcast::<T>()
+            .map(|msg| *msg)
+            .map_err(|_| {
+                SerializeError::WrongType(msg.manifest.clone(), any::type_name::<T>().to_string())
+            })
+    }
+
+    /// Deserialize a message to the type registered with its manifest
+    pub fn deserialize_any(
+        &self,
+        msg: &SerializedMessage,
+    ) -> Result<Box<dyn Any + Send>, SerializeError> {
+        let reg = self
+            .registry
+            .read()
+            .unwrap()
+            .by_manifest
+            .get(&msg.manifest)
+            .cloned()
+            .ok_or_else(|| SerializeError::UnknownManifest(msg.manifest.clone()))?;
+
+        (reg.decode)(&msg.bytes)
+    }
+}
+
+/// Error type when serializing or deserializing a message fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SerializeError {
+    /// The message type has not been registered
+    NotRegistered(String),
+
+    /// No message type has been registered with the manifest
+    UnknownManifest(String),
+
+    /// The manifest is already registered for another type
+    DuplicateManifest(String),
+
+    /// The message type is already registered with another manifest
+    DuplicateType(String, String),
+
+    /// The manifest is registered for a type other than the requested type
+    WrongType(String, String),
+
+    Encode(String),
+
+    Decode(String),
+}
+
+impl std::error::Error for SerializeError {}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NotRegistered(ref ty) => f.write_str(&format!(
+                "Failed to serialize message. Cause: Type {} is not registered",
+                ty
+            )),
+            Self::UnknownManifest(ref manifest) => f.write_str(&format!(
+                "Failed to deserialize message. Cause: No type registered for manifest {}",
+                manifest
+            )),
+            Self::DuplicateManifest(ref manifest) => f.write_str(&format!(
+                "Failed to register message type. Cause: Manifest {} already registered",
+                manifest
+            )),
+            Self::DuplicateType(ref ty, ref manifest) => f.write_str(&format!(
+                "Failed to register message type. Cause: Type {} already registered as {}",
+                ty, manifest
+            )),
+            Self::WrongType(ref manifest, ref ty) => f.write_str(&format!(
+                "Failed to deserialize message. Cause: Manifest {} is not registered for type {}",
+                manifest, ty
+            )),
+            Self::Encode(ref err) => {
+                f.write_str(&format!("Failed to serialize message. Cause: {}", err))
+            }
+            Self::Decode(ref err) => {
+                f.write_str(&format!("Failed to deserialize message. Cause: {}", err))
+            }
+        }
+    }
+}
@@ -21,8 +21,13 @@ impl fmt::Display for InvalidName {
     }
 }
 
+/// Validate an actor path, or a selection of actors
+///
+/// Besides actor names, a selection can contain `..`, `*` and `**`
+/// segments, and globs with `*`, `?` and character classes such as
+/// `[0-9]` or `[!a-c]`.
 pub fn validate_path(path: &str) -> Result<(), InvalidPath> {
-    let rgx = Regex::new(r"^[a-zA-Z0-9/*._-]+$").unwrap();
+    let rgx = Regex::new(r"^(?:[a-zA-Z0-9/*?._-]|\[[!^]?[a-zA-Z0-9_-]+\])+$").unwrap();
     if rgx.is_match(path) {
         Ok(())
     } else {
@@ -30,11 +35,35 @@ pub fn validate_path(path: &str) -> Result<(), InvalidPath> {
     }
 }
 
+/// Validate the path of a single actor
+///
+/// Unlike `validate_path`, only actor names separated by `/` are
+/// accepted, so a selection isn't mistaken for an actor.
+pub fn validate_actor_path(path: &str) -> Result<(), InvalidPath> {
+    let valid = match path.strip_prefix('/') {
+        Some("") => true, // the root actor
+        Some(names) => names.split('/').all(|name| validate_name(name).is_ok()),
+        None => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(InvalidPath { path: path.into() })
+    }
+}
+
 #[derive(Debug)]
 pub struct InvalidPath {
     path: String,
 }
 
+impl InvalidPath {
+    pub(crate) fn new(path: &str) -> Self {
+        Self { path: path.into() }
+    }
+}
+
 impl fmt::Display for InvalidPath {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(&format!("\"{}\". {}", self.path, self.to_string()))
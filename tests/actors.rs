@@ -117,6 +117,170 @@ fn actor_try_tell() {
     p_assert_eq!(listen, ());
 }
 
+#[test]
+fn actor_typed_as() {
+    let sys = ActorSystem::new().unwrap();
+
+    sys.actor_of::<Counter>("me").unwrap();
+    let actor = sys.user_root().children().next().unwrap();
+
+    assert!(actor.accepts::<CounterMsg>());
+    assert!(!actor.accepts::<Add>());
+    assert!(actor.typed_as::<String>().is_none());
+
+    // the recovered ref is an ordinary typed ref
+    let actor: ActorRef<CounterMsg> = actor.typed_as().unwrap();
+    assert_eq!(actor.path().to_string(), "/user/me");
+
+    let (probe, listen) = probe();
+    actor.tell(TestProbe(probe), None);
+
+    for _ in 0..1_000_000 {
+        actor.tell(Add, None);
+    }
+
+    p_assert_eq!(listen, ());
+}
+
+/// A service with its own protocol, replying to whoever asks
+#[derive(Clone, Debug)]
+pub struct Ping(u32, ActorRef<Pong>);
+
+#[derive(Clone, Debug)]
+pub struct Pong(u32);
+
+#[derive(Default)]
+struct Service;
+
+impl Actor for Service {
+    type Msg = Ping;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        msg.1.tell(Pong(msg.0), None);
+    }
+}
+
+/// Hands the references it's asked to reply to over to the test
+struct Keeper {
+    probe: ChannelProbe<(), ActorRef<Pong>>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), ActorRef<Pong>>> for Keeper {
+    fn create_args(probe: ChannelProbe<(), ActorRef<Pong>>) -> Self {
+        Keeper { probe }
+    }
+}
+
+impl Actor for Keeper {
+    type Msg = Ping;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg.1);
+    }
+}
+
+struct DeadLetterSub {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for DeadLetterSub {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        DeadLetterSub { probe }
+    }
+}
+
+impl Actor for DeadLetterSub {
+    type Msg = DeadLetter;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system.dead_letters().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: "*".into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".to_string());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg.msg);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ClientMsg {
+    Start(ActorRef<Ping>, ChannelProbe<(), String>),
+    Pong(u32, u32),
+}
+
+#[derive(Default)]
+struct Client {
+    probe: Option<ChannelProbe<(), String>>,
+    starts: u32,
+}
+
+impl Actor for Client {
+    type Msg = ClientMsg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            ClientMsg::Start(service, probe) => {
+                self.probe = Some(probe);
+                self.starts += 1;
+                let start = self.starts;
+                let reply_to =
+                    ctx.message_adapter(move |pong: Pong| ClientMsg::Pong(pong.0, start));
+                service.tell(Ping(1, reply_to), None);
+            }
+            ClientMsg::Pong(n, start) => {
+                let probe = self.probe.as_ref().unwrap();
+                probe.event(format!("pong {} from start {}", n, start));
+            }
+        }
+    }
+}
+
+#[test]
+fn actor_message_adapter() {
+    let sys = ActorSystem::new().unwrap();
+
+    let service = sys.actor_of::<Service>("service").unwrap();
+    let client = sys.actor_of::<Client>("client").unwrap();
+
+    let (keeper_probe, keeper_listen) = probe();
+    let keeper = sys
+        .actor_of_args::<Keeper, _>("keeper", keeper_probe)
+        .unwrap();
+
+    let (dl_probe, dl_listen) = probe();
+    sys.actor_of_args::<DeadLetterSub, _>("dl-subscriber", dl_probe)
+        .unwrap();
+    p_assert_eq!(dl_listen, "subscribed".to_string());
+
+    let (probe, listen) = probe();
+    client.tell(ClientMsg::Start(service.clone(), probe.clone()), None);
+    p_assert_eq!(listen, "pong 1 from start 1".to_string());
+    client.tell(ClientMsg::Start(service, probe.clone()), None);
+    p_assert_eq!(listen, "pong 1 from start 2".to_string());
+
+    // the adapter is a reference to the client, not a child actor
+    client.tell(ClientMsg::Start(keeper, probe), None);
+    let adapter = keeper_listen.recv();
+    assert_eq!(adapter.path(), client.path());
+    assert!(!client.has_children());
+    assert!(sys.select("client/*").unwrap().resolve().is_empty());
+
+    adapter.tell(Pong(2), None);
+    p_assert_eq!(listen, "pong 2 from start 3".to_string());
+
+    // once the client stops, messages sent to the adapter are dead letters
+    sys.stop(&client);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    adapter.tell(Pong(3), None);
+    p_assert_eq!(dl_listen, "Pong(3, 3)".to_string());
+}
+
 #[derive(Default)]
 struct Parent {
     probe: Option<TestProbe>,
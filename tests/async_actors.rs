@@ -0,0 +1,367 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use actors_rs::*;
+
+use futures::{channel::oneshot, future::BoxFuture};
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+
+/// Completes after `millis`, without blocking the caller's thread
+fn delay(millis: u64) -> oneshot::Receiver<()> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(millis));
+        let _ = tx.send(());
+    });
+    rx
+}
+
+#[derive(Clone, Debug)]
+pub enum CounterMsg {
+    Probe(ChannelProbe<(), String>),
+    Add(u64),
+    Panic,
+}
+
+/// Adds up the delays it waits for
+#[derive(Default)]
+struct Counter {
+    probe: Option<ChannelProbe<(), String>>,
+    total: u64,
+}
+
+impl AsyncActor for Counter {
+    type Msg = CounterMsg;
+
+    fn recv<'a>(
+        &'a mut self,
+        _ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        _sender: Sender,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            match msg {
+                CounterMsg::Probe(probe) => self.probe = Some(probe),
+                CounterMsg::Add(millis) => {
+                    delay(millis).await.unwrap();
+                    self.total += millis;
+                    if let Some(ref probe) = self.probe {
+                        probe.event(format!("total {}", self.total));
+                    }
+                }
+                CounterMsg::Panic => {
+                    delay(10).await.unwrap();
+                    panic!("// TEST PANIC // TEST PANIC // TEST PANIC //");
+                }
+            }
+        })
+    }
+}
+
+/// Passes messages to the async actor it wraps, and some directly to its `recv`
+struct Wrapper {
+    counter: Async<Counter>,
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Wrapper {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Wrapper {
+            counter: Async(Counter::default()),
+            probe,
+        }
+    }
+}
+
+impl Actor for Wrapper {
+    type Msg = CounterMsg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let probe = CounterMsg::Probe(self.probe.clone());
+        self.counter.recv(ctx, probe, None);
+        self.counter.recv(ctx, CounterMsg::Add(5), None);
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.counter.recv(ctx, msg, sender);
+    }
+
+    fn recv_future<'a>(
+        &'a mut self,
+        ctx: &'a Context<Self::Msg>,
+        msg: Self::Msg,
+        sender: Sender,
+    ) -> Option<BoxFuture<'a, ()>> {
+        self.counter.recv_future(ctx, msg, sender)
+    }
+}
+
+#[test]
+fn async_actor_recv_is_handled_through_the_mailbox() {
+    let sys = ActorSystem::new().unwrap();
+
+    // the messages passed to `recv` are handled, without panicking
+    // or blocking the wrapper's thread
+    let (probe, listen) = probe();
+    sys.actor_of_args::<Wrapper, _>("wrapper", probe).unwrap();
+    p_assert_eq!(listen, "total 5".to_string());
+}
+
+#[test]
+fn async_actor_handles_one_message_at_a_time() {
+    let sys = ActorSystem::new().unwrap();
+
+    let counter = sys.actor_of::<Async<Counter>>("counter").unwrap();
+
+    let (probe, listen) = probe();
+    counter.tell(CounterMsg::Probe(probe), None);
+
+    // the longest delay is handled first and completes before the next
+    counter.tell(CounterMsg::Add(200), None);
+    counter.tell(CounterMsg::Add(10), None);
+    counter.tell(CounterMsg::Add(1), None);
+
+    p_assert_eq!(listen, "total 200".to_string());
+    p_assert_eq!(listen, "total 210".to_string());
+    p_assert_eq!(listen, "total 211".to_string());
+}
+
+#[test]
+fn async_actor_does_not_block_executor() {
+    let sys = ActorSystem::new().unwrap();
+
+    // more actors waiting than there are threads in the executor
+    for i in 0..50 {
+        let waiting = sys
+            .actor_of::<Async<Counter>>(&format!("waiting-{}", i))
+            .unwrap();
+        waiting.tell(CounterMsg::Add(2000), None);
+    }
+
+    let (probe, listen) = probe();
+    let start = std::time::Instant::now();
+    let counter = sys.actor_of::<Async<Counter>>("counter").unwrap();
+    counter.tell(CounterMsg::Probe(probe), None);
+    counter.tell(CounterMsg::Add(1), None);
+
+    p_assert_eq!(listen, "total 1".to_string());
+    assert!(start.elapsed() < std::time::Duration::from_millis(1500));
+}
+
+#[test]
+fn async_actor_panic_is_supervised() {
+    let sys = ActorSystem::new().unwrap();
+
+    let counter = sys.actor_of::<Async<Counter>>("counter").unwrap();
+
+    let (probe, listen) = probe();
+    counter.tell(CounterMsg::Probe(probe.clone()), None);
+    counter.tell(CounterMsg::Add(5), None);
+    p_assert_eq!(listen, "total 5".to_string());
+
+    // the actor is restarted with a new state
+    counter.tell(CounterMsg::Panic, None);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    counter.tell(CounterMsg::Probe(probe), None);
+    counter.tell(CounterMsg::Add(1), None);
+    p_assert_eq!(listen, "total 1".to_string());
+}
+
+#[derive(Clone, Debug)]
+pub enum FetcherMsg {
+    Fetch(u64),
+    FetchFor(u64, ActorRef<String>),
+    FetchFlagged(u64, Arc<AtomicBool>),
+    Fetched(u64),
+}
+
+/// Pipes the result of futures to itself or to another actor
+struct Fetcher {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Fetcher {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Fetcher { probe }
+    }
+}
+
+impl Actor for Fetcher {
+    type Msg = FetcherMsg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            FetcherMsg::Fetch(millis) => {
+                let fetch = async move {
+                    delay(millis).await.unwrap();
+                    millis
+                };
+                ctx.pipe_to(fetch, FetcherMsg::Fetched).unwrap();
+            }
+            FetcherMsg::FetchFor(millis, actor) => {
+                let fetch = async move {
+                    delay(millis).await.unwrap();
+                    millis
+                };
+                ctx.pipe_to_actor(fetch, actor, |millis| format!("fetched {} for", millis))
+                    .unwrap();
+            }
+            FetcherMsg::FetchFlagged(millis, flag) => {
+                let fetch = async move {
+                    delay(millis).await.unwrap();
+                    flag.store(true, Ordering::SeqCst);
+                    millis
+                };
+                ctx.pipe_to(fetch, FetcherMsg::Fetched).unwrap();
+                self.probe.event("fetching".into());
+            }
+            FetcherMsg::Fetched(millis) => self.probe.event(format!("fetched {}", millis)),
+        }
+    }
+}
+
+struct Reporter {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Reporter {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Reporter { probe }
+    }
+}
+
+impl Actor for Reporter {
+    type Msg = String;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+#[test]
+fn pipe_to_sends_future_output() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    let fetcher = sys
+        .actor_of_args::<Fetcher, _>("fetcher", probe.clone())
+        .unwrap();
+    let reporter = sys.actor_of_args::<Reporter, _>("reporter", probe).unwrap();
+
+    // the actor handles other messages while the future runs
+    fetcher.tell(FetcherMsg::Fetch(200), None);
+    fetcher.tell(FetcherMsg::Fetch(10), None);
+    p_assert_eq!(listen, "fetched 10".to_string());
+    p_assert_eq!(listen, "fetched 200".to_string());
+
+    fetcher.tell(FetcherMsg::FetchFor(10, reporter), None);
+    p_assert_eq!(listen, "fetched 10 for".to_string());
+}
+
+#[test]
+fn pipe_to_cancelled_when_actor_stops() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    let fetcher = sys.actor_of_args::<Fetcher, _>("fetcher", probe).unwrap();
+
+    let flag = Arc::new(AtomicBool::new(false));
+    fetcher.tell(FetcherMsg::FetchFlagged(200, flag.clone()), None);
+    p_assert_eq!(listen, "fetching".to_string());
+
+    // the future is dropped before it completes
+    sys.stop(&fetcher);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert!(!flag.load(Ordering::SeqCst));
+}
+
+#[derive(Clone, Debug)]
+pub enum SpawnerMsg {
+    Spawn(u64, Arc<AtomicBool>),
+    SpawnUntilRestart(u64, Arc<AtomicBool>),
+    Panic,
+}
+
+/// Spawns futures that raise a flag when they complete
+struct Spawner {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Spawner {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Spawner { probe }
+    }
+}
+
+impl Actor for Spawner {
+    type Msg = SpawnerMsg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        let flagged = |millis, flag: Arc<AtomicBool>| async move {
+            delay(millis).await.unwrap();
+            flag.store(true, Ordering::SeqCst);
+        };
+
+        match msg {
+            SpawnerMsg::Spawn(millis, flag) => ctx.spawn(flagged(millis, flag)).unwrap(),
+            SpawnerMsg::SpawnUntilRestart(millis, flag) => {
+                ctx.spawn_until_restart(flagged(millis, flag)).unwrap()
+            }
+            SpawnerMsg::Panic => panic!("// TEST PANIC // TEST PANIC // TEST PANIC //"),
+        }
+        self.probe.event("spawned".into());
+    }
+}
+
+#[test]
+fn spawned_futures_aborted_when_actor_stops() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    let spawner = sys.actor_of_args::<Spawner, _>("spawner", probe).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    spawner.tell(SpawnerMsg::Spawn(10, done.clone()), None);
+    p_assert_eq!(listen, "spawned".to_string());
+
+    // futures complete while the actor runs
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert!(done.load(Ordering::SeqCst));
+
+    let aborted = Arc::new(AtomicBool::new(false));
+    spawner.tell(SpawnerMsg::Spawn(200, aborted.clone()), None);
+    p_assert_eq!(listen, "spawned".to_string());
+
+    sys.stop(&spawner);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert!(!aborted.load(Ordering::SeqCst));
+}
+
+#[test]
+fn spawned_futures_aborted_when_actor_restarts() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    let spawner = sys.actor_of_args::<Spawner, _>("spawner", probe).unwrap();
+
+    let kept = Arc::new(AtomicBool::new(false));
+    spawner.tell(SpawnerMsg::Spawn(200, kept.clone()), None);
+    p_assert_eq!(listen, "spawned".to_string());
+
+    let aborted = Arc::new(AtomicBool::new(false));
+    spawner.tell(SpawnerMsg::SpawnUntilRestart(200, aborted.clone()), None);
+    p_assert_eq!(listen, "spawned".to_string());
+
+    // only the future spawned until restart is aborted
+    spawner.tell(SpawnerMsg::Panic, None);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert!(kept.load(Ordering::SeqCst));
+    assert!(!aborted.load(Ordering::SeqCst));
+}
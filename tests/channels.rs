@@ -348,3 +348,577 @@ fn channel_dead_letters() {
 
     p_assert_eq!(listen, ());
 }
+
+// *** Group subscription test ***
+struct GroupMember {
+    chan: ChannelRef<SomeMessage>,
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<(ChannelRef<SomeMessage>, ChannelProbe<(), String>)> for GroupMember {
+    fn create_args((chan, probe): (ChannelRef<SomeMessage>, ChannelProbe<(), String>)) -> Self {
+        GroupMember { chan, probe }
+    }
+}
+
+impl Actor for GroupMember {
+    type Msg = SomeMessage;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.chan.tell(
+            SubscribeGroup {
+                actor: Box::new(ctx.myself()),
+                topic: "my-topic".into(),
+                group: "workers".into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {
+        self.probe.event(ctx.myself().name().into());
+    }
+}
+
+#[test]
+fn channel_publish_group() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<SomeMessage> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    for name in &["member-1", "member-2"] {
+        sys.actor_of_args::<GroupMember, _>(name, (chan.clone(), probe.clone()))
+            .unwrap();
+        p_assert_eq!(listen, "subscribed".to_string());
+    }
+
+    for _ in 0..4 {
+        chan.tell(
+            Publish {
+                msg: SomeMessage,
+                topic: "my-topic".into(),
+            },
+            None,
+        );
+    }
+
+    // each message is received by one member of the group, in turn
+    let mut received: Vec<String> = (0..4).map(|_| listen.recv()).collect();
+    received.sort();
+    assert_eq!(
+        received,
+        vec!["member-1", "member-1", "member-2", "member-2"]
+    );
+}
+
+// *** Wildcard topic test ***
+struct WildcardSubscriber {
+    chan: ChannelRef<String>,
+    topic: Topic,
+    probe: ChannelProbe<(), String>,
+}
+
+type WildcardArgs = (ChannelRef<String>, Topic, ChannelProbe<(), String>);
+
+impl ActorFactoryArgs<WildcardArgs> for WildcardSubscriber {
+    fn create_args((chan, topic, probe): WildcardArgs) -> Self {
+        WildcardSubscriber { chan, topic, probe }
+    }
+}
+
+impl Actor for WildcardSubscriber {
+    type Msg = String;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.chan.tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: self.topic.clone(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(format!("{} {}", self.topic, msg));
+    }
+}
+
+#[test]
+fn channel_publish_wildcard_topics() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    for (name, topic) in &[("eu", "orders.eu.*"), ("orders", "orders.#")] {
+        let args = (chan.clone(), Topic::from(*topic), probe.clone());
+        sys.actor_of_args::<WildcardSubscriber, _>(name, args)
+            .unwrap();
+        p_assert_eq!(listen, "subscribed".to_string());
+    }
+
+    let publish = |topic: &str| {
+        chan.tell(
+            Publish {
+                msg: topic.to_string(),
+                topic: topic.into(),
+            },
+            None,
+        );
+    };
+
+    // `*` matches one segment and `#` matches any number of segments
+    publish("orders.eu.created");
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(
+        received,
+        vec![
+            "orders.# orders.eu.created",
+            "orders.eu.* orders.eu.created"
+        ]
+    );
+
+    publish("orders.eu.created.late");
+    publish("invoices.eu.created");
+    publish("orders");
+    p_assert_eq!(listen, "orders.# orders.eu.created.late".to_string());
+    p_assert_eq!(listen, "orders.# orders".to_string());
+}
+
+#[test]
+fn topic_matches_wildcards() {
+    let topic = |t: &str| Topic::from(t);
+
+    assert!(topic("orders.*").matches(&topic("orders.eu")));
+    assert!(!topic("orders.*").matches(&topic("orders")));
+    assert!(!topic("orders.*").matches(&topic("orders.eu.created")));
+    assert!(topic("orders.#").matches(&topic("orders")));
+    assert!(topic("orders.#.created").matches(&topic("orders.eu.de.created")));
+    assert!(topic("#.created").matches(&topic("orders.created")));
+    assert!(topic("*").matches(&topic("orders.eu.created")));
+    assert!(!topic("orders.eu").matches(&topic("orders.us")));
+}
+
+// *** Terminated subscriber test ***
+struct DeadLetterReporter {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for DeadLetterReporter {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        DeadLetterReporter { probe }
+    }
+}
+
+impl Actor for DeadLetterReporter {
+    type Msg = DeadLetter;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system.dead_letters().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe
+            .event(format!("dead letter {} {}", msg.recipient.name(), msg.msg));
+    }
+}
+
+#[test]
+fn channel_unsubscribes_terminated_actors() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<DeadLetterReporter, _>("dl-reporter", probe.clone())
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    let mut subs = Vec::new();
+    for name in &["stopped", "running"] {
+        let args = (chan.clone(), Topic::from("my-topic"), probe.clone());
+        subs.push(
+            sys.actor_of_args::<WildcardSubscriber, _>(name, args)
+                .unwrap(),
+        );
+        p_assert_eq!(listen, "subscribed".to_string());
+    }
+
+    // the subscriber stops without unsubscribing
+    sys.stop(&subs[0]);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    chan.tell(
+        Publish {
+            msg: "published".to_string(),
+            topic: "my-topic".into(),
+        },
+        None,
+    );
+    p_assert_eq!(listen, "my-topic published".to_string());
+
+    // the published message wasn't sent to the stopped subscriber,
+    // so the first dead letter is the one sent directly
+    subs[0].tell("direct".to_string(), None);
+    p_assert_eq!(listen, "dead letter stopped \"direct\"".to_string());
+}
+
+// *** Retained messages test ***
+fn publish_to(chan: &ChannelRef<String>, topic: &str, msg: &str) {
+    chan.tell(
+        Publish {
+            msg: msg.to_string(),
+            topic: topic.into(),
+        },
+        None,
+    );
+}
+
+#[test]
+fn channel_replays_last_value() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> =
+        channel_with_retention("my-chan", Retention::LastValue, &sys).unwrap();
+
+    publish_to(&chan, "orders.eu", "eu-1");
+    publish_to(&chan, "orders.eu", "eu-2");
+    publish_to(&chan, "orders.us", "us-1");
+    publish_to(&chan, "invoices.eu", "invoice-1");
+
+    // the last message of each matching topic is replayed on subscribe
+    let (probe, listen) = probe();
+    let args = (chan.clone(), Topic::from("orders.*"), probe);
+    sys.actor_of_args::<WildcardSubscriber, _>("sub", args)
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    p_assert_eq!(listen, "orders.* eu-2".to_string());
+    p_assert_eq!(listen, "orders.* us-1".to_string());
+
+    // followed by the messages published after subscribing
+    publish_to(&chan, "orders.eu", "eu-3");
+    p_assert_eq!(listen, "orders.* eu-3".to_string());
+}
+
+#[test]
+fn channel_replays_bounded_buffer() {
+    let sys = ActorSystem::new().unwrap();
+    let last: ChannelRef<String> =
+        channel_with_retention("last", Retention::Last(2), &sys).unwrap();
+    let within: ChannelRef<String> = channel_with_retention(
+        "within",
+        Retention::Within(std::time::Duration::from_millis(300)),
+        &sys,
+    )
+    .unwrap();
+
+    for msg in &["1", "2", "3"] {
+        publish_to(&last, "my-topic", msg);
+    }
+    publish_to(&within, "my-topic", "expired");
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    publish_to(&within, "my-topic", "recent");
+
+    let (probe, listen) = probe();
+    let args = (last, Topic::from("my-topic"), probe.clone());
+    sys.actor_of_args::<WildcardSubscriber, _>("last-sub", args)
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    p_assert_eq!(listen, "my-topic 2".to_string());
+    p_assert_eq!(listen, "my-topic 3".to_string());
+
+    let args = (within, Topic::from("my-topic"), probe);
+    sys.actor_of_args::<WildcardSubscriber, _>("within-sub", args)
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    p_assert_eq!(listen, "my-topic recent".to_string());
+}
+
+// *** Filtered subscription test ***
+struct FilteredSubscriber {
+    chan: ChannelRef<String>,
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<(ChannelRef<String>, ChannelProbe<(), String>)> for FilteredSubscriber {
+    fn create_args((chan, probe): (ChannelRef<String>, ChannelProbe<(), String>)) -> Self {
+        FilteredSubscriber { chan, probe }
+    }
+}
+
+impl Actor for FilteredSubscriber {
+    type Msg = String;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.chan.tell(
+            SubscribeFiltered {
+                actor: Box::new(ctx.myself()),
+                topic: "orders".into(),
+                filter: std::sync::Arc::new(|msg: &String| msg.starts_with("large")),
+            },
+            None,
+        );
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+#[test]
+fn channel_publish_filtered() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> =
+        channel_with_retention("my-chan", Retention::Last(2), &sys).unwrap();
+
+    publish_to(&chan, "orders", "large-0");
+    publish_to(&chan, "orders", "small-0");
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<FilteredSubscriber, _>("sub", (chan.clone(), probe))
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    // only the messages matching the filter are sent, including when replayed
+    p_assert_eq!(listen, "large-0".to_string());
+
+    for msg in &["small-1", "large-1", "small-2", "large-2"] {
+        publish_to(&chan, "orders", msg);
+    }
+    p_assert_eq!(listen, "large-1".to_string());
+    p_assert_eq!(listen, "large-2".to_string());
+}
+
+// *** Shared payload test ***
+struct SharedSubscriber {
+    probe: ChannelProbe<(), (String, usize)>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), (String, usize)>> for SharedSubscriber {
+    fn create_args(probe: ChannelProbe<(), (String, usize)>) -> Self {
+        SharedSubscriber { probe }
+    }
+}
+
+impl Actor for SharedSubscriber {
+    type Msg = std::sync::Arc<String>;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        let payload = std::sync::Arc::as_ptr(&msg) as usize;
+        self.probe.event((ctx.myself().name().to_string(), payload));
+    }
+}
+
+#[test]
+fn channel_publish_shared_payload_once() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<std::sync::Arc<String>> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    let a = sys
+        .actor_of_args::<SharedSubscriber, _>("a", probe.clone())
+        .unwrap();
+    let b = sys
+        .actor_of_args::<SharedSubscriber, _>("b", probe)
+        .unwrap();
+
+    // `a` is subscribed to both all topics and the topic
+    for (actor, topic) in &[
+        (&a, Topic::from(All)),
+        (&a, "my-topic".into()),
+        (&b, "my-topic".into()),
+    ] {
+        chan.tell(
+            Subscribe {
+                actor: Box::new((*actor).clone()),
+                topic: topic.clone(),
+            },
+            None,
+        );
+    }
+
+    // each subscriber is sent the same payload, once
+    let payload = std::sync::Arc::new("payload".to_string());
+    let ptr = std::sync::Arc::as_ptr(&payload) as usize;
+    chan.tell(
+        Publish {
+            msg: payload,
+            topic: "my-topic".into(),
+        },
+        None,
+    );
+
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(
+        received,
+        vec![("a".to_string(), ptr), ("b".to_string(), ptr)]
+    );
+
+    let other = std::sync::Arc::new("other".to_string());
+    let other_ptr = std::sync::Arc::as_ptr(&other) as usize;
+    chan.tell(
+        Publish {
+            msg: other,
+            topic: "other-topic".into(),
+        },
+        None,
+    );
+    p_assert_eq!(listen, ("a".to_string(), other_ptr));
+}
+
+// *** Contramap test ***
+struct Reporter {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Reporter {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Reporter { probe }
+    }
+}
+
+impl Actor for Reporter {
+    type Msg = String;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+#[test]
+fn channel_subscribe_contramap() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<u32> = channel("numbers", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    let reporter = sys.actor_of_args::<Reporter, _>("reporter", probe).unwrap();
+
+    // the `String` actor is subscribed to a channel of `u32`
+    chan.tell(
+        Subscribe {
+            actor: Box::new(reporter.contramap(|n: u32| format!("number {}", n))),
+            topic: "my-topic".into(),
+        },
+        None,
+    );
+    chan.tell(
+        Publish {
+            msg: 1,
+            topic: "my-topic".into(),
+        },
+        None,
+    );
+    p_assert_eq!(listen, "number 1".to_string());
+
+    // a `BoxedTell` can be converted as well, and conversions chained
+    let boxed: BoxedTell<String> = Box::new(reporter);
+    let flag = boxed
+        .contramap(|n: u32| format!("flag {}", n))
+        .contramap(|flag: bool| u32::from(flag));
+    flag.tell(true, None);
+    p_assert_eq!(listen, "flag 1".to_string());
+}
+
+#[test]
+fn channel_subscribe_directly_and_contramap() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    let reporter = sys.actor_of_args::<Reporter, _>("reporter", probe).unwrap();
+    let converted = reporter.contramap(|msg: String| format!("converted {}", msg));
+
+    // the actor is subscribed both directly and through a conversion
+    let subscribe = |actor: BoxedTell<String>| {
+        chan.tell(
+            Subscribe {
+                actor,
+                topic: "my-topic".into(),
+            },
+            None,
+        );
+    };
+    let unsubscribe = |actor: BoxedTell<String>| {
+        chan.tell(
+            Unsubscribe {
+                actor,
+                topic: "my-topic".into(),
+            },
+            None,
+        );
+    };
+    let publish = |msg: &str| {
+        chan.tell(
+            Publish {
+                msg: msg.to_string(),
+                topic: "my-topic".into(),
+            },
+            None,
+        );
+    };
+    subscribe(Box::new(reporter.clone()));
+    subscribe(converted.box_clone());
+
+    // it's sent the message through each subscription
+    publish("a");
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(received, vec!["a".to_string(), "converted a".to_string()]);
+
+    // unsubscribing one subscription keeps the other
+    unsubscribe(converted.box_clone());
+    publish("b");
+    p_assert_eq!(listen, "b".to_string());
+
+    subscribe(converted.box_clone());
+    unsubscribe(Box::new(reporter));
+    publish("c");
+    p_assert_eq!(listen, "converted c".to_string());
+}
+
+#[test]
+fn channel_unsubscribes_every_subscription_of_terminated_actors() {
+    let sys = ActorSystem::new().unwrap();
+    let chan: ChannelRef<String> = channel("my-chan", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<DeadLetterReporter, _>("dl-reporter", probe.clone())
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    // the actor has two filtered subscriptions to the same topic
+    let reporter = sys.actor_of_args::<Reporter, _>("stopped", probe).unwrap();
+    for prefix in &["large", "l"] {
+        chan.tell(
+            SubscribeFiltered {
+                actor: Box::new(reporter.clone()),
+                topic: "orders".into(),
+                filter: std::sync::Arc::new(move |msg: &String| msg.starts_with(prefix)),
+            },
+            None,
+        );
+    }
+    publish_to(&chan, "orders", "large-1");
+    p_assert_eq!(listen, "large-1".to_string());
+
+    // the subscriber stops without unsubscribing
+    sys.stop(&reporter);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // neither subscription sends the published message to dead letters,
+    // so the first dead letter is the one sent directly
+    publish_to(&chan, "orders", "large-2");
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    reporter.tell("direct".to_string(), None);
+    p_assert_eq!(listen, "dead letter stopped \"direct\"".to_string());
+}
@@ -0,0 +1,242 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors_rs::cluster::{Cluster, ClusterEvent, MemberStatus, PhiAccrualFailureDetector};
+use actors_rs::remote::Remoting;
+use actors_rs::system::SystemBuilder;
+use actors_rs::*;
+use config::Config;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe, ChannelProbeReceive};
+use riker_testkit::probe::{Probe, ProbeReceive};
+
+type MemberProbe = ChannelProbe<(), String>;
+
+struct Listener {
+    events: ChannelRef<ClusterEvent>,
+    probe: MemberProbe,
+}
+
+impl ActorFactoryArgs<(ChannelRef<ClusterEvent>, MemberProbe)> for Listener {
+    fn create_args((events, probe): (ChannelRef<ClusterEvent>, MemberProbe)) -> Self {
+        Listener { events, probe }
+    }
+}
+
+impl Actor for Listener {
+    type Msg = ClusterEvent;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.events.tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".to_string());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        let evt = match msg {
+            ClusterEvent::MemberUp(m) => format!("up {}", m.addr),
+            ClusterEvent::MemberUnreachable(m) => format!("unreachable {}", m.addr),
+            ClusterEvent::MemberReachable(m) => format!("reachable {}", m.addr),
+            ClusterEvent::MemberRemoved(m) => format!("removed {}", m.addr),
+        };
+        self.probe.event(evt);
+    }
+}
+
+fn config() -> Config {
+    let mut cfg = load_config();
+    cfg.set("remote.reconnect_interval_millis", 50).unwrap();
+    cfg.set("remote.reconnect_attempts", 1).unwrap();
+    cfg.set("cluster.heartbeat_interval_millis", 100).unwrap();
+    cfg.set("cluster.gossip_interval_millis", 100).unwrap();
+    cfg.set("cluster.acceptable_heartbeat_pause_millis", 300)
+        .unwrap();
+    cfg.set("cluster.remove_unreachable_after_millis", 500)
+        .unwrap();
+    cfg
+}
+
+fn node(seeds: &[SocketAddr]) -> (ActorSystem, Remoting, Cluster) {
+    node_with(config(), "127.0.0.1:0", seeds)
+}
+
+fn node_with(cfg: Config, addr: &str, seeds: &[SocketAddr]) -> (ActorSystem, Remoting, Cluster) {
+    // all nodes of a cluster use the same system name
+    let sys = SystemBuilder::new()
+        .name("cluster")
+        .cfg(cfg)
+        .create()
+        .unwrap();
+    let remote = Remoting::start(&sys, addr).unwrap();
+    let cluster = Cluster::join(&remote, seeds).unwrap();
+    (sys, remote, cluster)
+}
+
+fn listen(sys: &ActorSystem, cluster: &Cluster) -> ChannelProbeReceive<String> {
+    let (probe, listen) = probe();
+    sys.actor_of_args::<Listener, _>("listener", (cluster.events().clone(), probe))
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    listen
+}
+
+/// Wait for an event, skipping any other events
+fn await_event(listen: &ChannelProbeReceive<String>, expected: &str) {
+    while listen.recv() != expected {}
+}
+
+fn uid(cluster: &Cluster, addr: SocketAddr) -> u64 {
+    let members = cluster.members();
+    members.iter().find(|m| m.addr == addr).unwrap().uid
+}
+
+fn await_members(cluster: &Cluster, expected: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while cluster.members().len() != expected {
+        assert!(Instant::now() < deadline, "cluster did not converge");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn cluster_membership() {
+    let (sys_a, _remote_a, cluster_a) = node(&[]);
+    let listen_a = listen(&sys_a, &cluster_a);
+    let seed = cluster_a.self_addr();
+
+    let (_sys_b, _remote_b, cluster_b) = node(&[seed]);
+    p_assert_eq!(listen_a, format!("up {}", cluster_b.self_addr()));
+
+    let (_sys_c, remote_c, cluster_c) = node(&[seed]);
+    p_assert_eq!(listen_a, format!("up {}", cluster_c.self_addr()));
+
+    // every node converges on the same members
+    for cluster in &[&cluster_a, &cluster_b, &cluster_c] {
+        await_members(cluster, 3);
+        assert!(cluster
+            .members()
+            .iter()
+            .all(|m| m.status == MemberStatus::Up));
+    }
+    assert_eq!(cluster_a.members(), cluster_c.members());
+
+    // a node that can't be reached is detected as unreachable and then removed
+    remote_c.stop();
+    p_assert_eq!(listen_a, format!("unreachable {}", cluster_c.self_addr()));
+    p_assert_eq!(listen_a, format!("removed {}", cluster_c.self_addr()));
+
+    await_members(&cluster_a, 2);
+    await_members(&cluster_b, 2);
+}
+
+#[test]
+fn cluster_leave() {
+    let (sys_a, _remote_a, cluster_a) = node(&[]);
+    let listen_a = listen(&sys_a, &cluster_a);
+
+    let (_sys_b, _remote_b, cluster_b) = node(&[cluster_a.self_addr()]);
+    p_assert_eq!(listen_a, format!("up {}", cluster_b.self_addr()));
+    await_members(&cluster_b, 2);
+
+    // a node that leaves is removed without becoming unreachable
+    cluster_b.leave();
+    p_assert_eq!(listen_a, format!("removed {}", cluster_b.self_addr()));
+    await_members(&cluster_a, 1);
+}
+
+#[test]
+fn cluster_restarted_node_rejoins() {
+    let (sys_a, _remote_a, cluster_a) = node(&[]);
+    let listen_a = listen(&sys_a, &cluster_a);
+    let seed = cluster_a.self_addr();
+
+    let (_sys_b, remote_b, cluster_b) = node(&[seed]);
+    let addr = cluster_b.self_addr();
+    p_assert_eq!(listen_a, format!("up {}", addr));
+    await_members(&cluster_a, 2);
+    let old_uid = uid(&cluster_a, addr);
+
+    remote_b.stop();
+    p_assert_eq!(listen_a, format!("unreachable {}", addr));
+    p_assert_eq!(listen_a, format!("removed {}", addr));
+
+    // a node restarted on the same address joins as a new incarnation
+    let (_sys_c, _remote_c, cluster_c) = node_with(config(), &addr.to_string(), &[seed]);
+    p_assert_eq!(listen_a, format!("up {}", addr));
+
+    await_members(&cluster_a, 2);
+    await_members(&cluster_c, 2);
+    assert_ne!(uid(&cluster_a, addr), old_uid);
+    assert_eq!(uid(&cluster_a, addr), uid(&cluster_c, addr));
+    assert_eq!(cluster_a.members(), cluster_c.members());
+}
+
+#[test]
+fn cluster_removed_node_leaves() {
+    let (sys_a, _remote_a, cluster_a) = node(&[]);
+    let listen_a = listen(&sys_a, &cluster_a);
+
+    // a node that sends heartbeats too rarely is removed by the other
+    // members, while it still considers them reachable
+    let mut cfg = config();
+    cfg.set("cluster.heartbeat_interval_millis", 2000).unwrap();
+    cfg.set("cluster.gossip_interval_millis", 2000).unwrap();
+    cfg.set("cluster.acceptable_heartbeat_pause_millis", 10000)
+        .unwrap();
+    cfg.set("cluster.remove_unreachable_after_millis", 10000)
+        .unwrap();
+    let (sys_b, _remote_b, cluster_b) = node_with(cfg, "127.0.0.1:0", &[cluster_a.self_addr()]);
+    let listen_b = listen(&sys_b, &cluster_b);
+    let addr = cluster_b.self_addr();
+
+    p_assert_eq!(listen_a, format!("up {}", addr));
+    p_assert_eq!(listen_a, format!("unreachable {}", addr));
+    p_assert_eq!(listen_a, format!("removed {}", addr));
+    assert!(cluster_b.is_up());
+
+    // the removed node learns it was removed when it next gossips,
+    // and leaves the cluster
+    await_event(&listen_b, &format!("removed {}", addr));
+    assert!(!cluster_b.is_up());
+    await_members(&cluster_a, 1);
+}
+
+#[test]
+fn phi_accrual_failure_detector() {
+    let mut detector = PhiAccrualFailureDetector::new(
+        8.0,
+        100,
+        Duration::from_millis(10),
+        Duration::from_millis(0),
+        Duration::from_millis(100),
+    );
+
+    let start = Instant::now();
+    assert_eq!(detector.phi(start), 0.0);
+
+    for i in 0..10 {
+        detector.heartbeat(start + Duration::from_millis(i * 100));
+    }
+    let last = start + Duration::from_millis(900);
+
+    // suspicion grows with the time since the last heartbeat
+    let on_time = detector.phi(last + Duration::from_millis(100));
+    let late = detector.phi(last + Duration::from_millis(200));
+    assert!(on_time < 1.0);
+    assert!(late > on_time);
+    assert!(detector.is_available(last + Duration::from_millis(100)));
+    assert!(!detector.is_available(last + Duration::from_millis(1000)));
+
+    // a heartbeat ends the suspicion
+    detector.heartbeat(last + Duration::from_millis(1000));
+    assert!(detector.is_available(last + Duration::from_millis(1050)));
+}
@@ -0,0 +1,211 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use std::net::SocketAddr;
+
+use actors_rs::remote::{DeployError, Remoting};
+use actors_rs::system::{JsonCodec, SystemBuilder};
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Job {
+    Run(String),
+    Fail,
+    Stop,
+}
+
+/// Reports what happens to it to the recorder of the system it runs on
+struct Worker {
+    label: String,
+    recorder: Option<(String, ActorSelection)>,
+}
+
+impl ActorFactoryArgs<String> for Worker {
+    fn create_args(label: String) -> Self {
+        Worker {
+            label,
+            recorder: None,
+        }
+    }
+}
+
+impl Worker {
+    fn report(&self, evt: &str) {
+        if let Some((ref host, ref recorder)) = self.recorder {
+            recorder.try_tell(format!("{} {} {}", self.label, evt, host), None);
+        }
+    }
+}
+
+impl Actor for Worker {
+    type Msg = Job;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let recorder = ctx.select("/user/recorder").unwrap();
+        self.recorder = Some((ctx.system.host().to_string(), recorder));
+        self.report("started");
+    }
+
+    fn post_stop(&mut self) {
+        self.report("stopped");
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        match msg {
+            Job::Run(job) => self.report(&job),
+            Job::Fail => panic!("// TEST PANIC // TEST PANIC // TEST PANIC //"),
+            Job::Stop => ctx.stop(ctx.myself()),
+        }
+    }
+}
+
+struct Unregistered;
+
+impl ActorFactoryArgs<String> for Unregistered {
+    fn create_args(_: String) -> Self {
+        Unregistered
+    }
+}
+
+impl Actor for Unregistered {
+    type Msg = Job;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {}
+}
+
+struct Recorder {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Recorder {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Recorder { probe }
+    }
+}
+
+impl Actor for Recorder {
+    type Msg = String;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+/// Deploys a worker on another system and reports when it terminates
+struct Supervisor {
+    remote: Remoting,
+    target: SocketAddr,
+    probe: ChannelProbe<(), String>,
+}
+
+type SupervisorArgs = (Remoting, SocketAddr, ChannelProbe<(), String>);
+
+impl ActorFactoryArgs<SupervisorArgs> for Supervisor {
+    fn create_args((remote, target, probe): SupervisorArgs) -> Self {
+        Supervisor {
+            remote,
+            target,
+            probe,
+        }
+    }
+}
+
+impl Actor for Supervisor {
+    type Msg = Job;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        self.remote
+            .actor_of_args_at::<Worker, _>(ctx, self.target, "worker", "remote".to_string())
+            .unwrap();
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        ctx.myself().children().for_each(|worker| {
+            worker.try_tell(msg.clone(), None).unwrap();
+        });
+    }
+
+    fn sys_recv(&mut self, _ctx: &Context<Self::Msg>, msg: SystemMsg, _sender: Sender) {
+        if let SystemMsg::Event(SystemEvent::ActorTerminated(terminated)) = msg {
+            self.probe
+                .event(format!("terminated {}", terminated.actor.name()));
+        }
+    }
+}
+
+fn node(name: &str, probe: ChannelProbe<(), String>) -> (ActorSystem, Remoting) {
+    let sys = SystemBuilder::new().name(name).create().unwrap();
+    sys.serialization()
+        .register::<Job, _>("test.Job", JsonCodec)
+        .unwrap();
+    sys.actor_of_args::<Recorder, _>("recorder", probe).unwrap();
+
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+    remote
+        .register_deployable::<Worker, String>("test.Worker")
+        .unwrap();
+    (sys, remote)
+}
+
+#[test]
+fn deploy_remote_actor() {
+    let (probe, listen) = probe();
+    let (sys_a, remote_a) = node("deploy-a", probe.clone());
+    let (_sys_b, remote_b) = node("deploy-b", probe);
+    let host_b = remote_b.local_addr();
+
+    // the actor is created on the target system from the serialized args
+    let worker = remote_a
+        .actor_of_args_at::<Worker, _>(&sys_a, host_b, "worker", "remote".to_string())
+        .unwrap();
+    p_assert_eq!(listen, format!("remote started {}", host_b));
+
+    worker.tell(Job::Run("job-1".into()), None);
+    p_assert_eq!(listen, format!("remote job-1 {}", host_b));
+
+    // stopping the local reference stops the actor
+    sys_a.stop(&worker);
+    p_assert_eq!(listen, format!("remote stopped {}", host_b));
+
+    // an actor that isn't registered can't be deployed
+    let err = remote_a
+        .actor_of_args_at::<Unregistered, _>(&sys_a, host_b, "unregistered", String::new())
+        .unwrap_err();
+    assert!(matches!(err, DeployError::NotRegistered(_)));
+}
+
+#[test]
+fn deployed_actor_is_supervised_by_local_parent() {
+    let (probe, listen) = probe();
+    let (sys_a, remote_a) = node("deploy-a", probe.clone());
+    let (_sys_b, remote_b) = node("deploy-b", probe.clone());
+    let host_b = remote_b.local_addr();
+
+    let supervisor = sys_a
+        .actor_of_args::<Supervisor, _>("supervisor", (remote_a, host_b, probe))
+        .unwrap();
+    p_assert_eq!(listen, format!("remote started {}", host_b));
+
+    // the parent's restart strategy deploys a new instance
+    supervisor.tell(Job::Fail, None);
+    p_assert_eq!(listen, format!("remote started {}", host_b));
+
+    supervisor.tell(Job::Run("job-2".into()), None);
+    p_assert_eq!(listen, format!("remote job-2 {}", host_b));
+
+    // the parent is notified when the deployed actor stops
+    supervisor.tell(Job::Stop, None);
+    let mut events = vec![listen.recv(), listen.recv()];
+    events.sort();
+    assert_eq!(
+        events,
+        vec![
+            format!("remote stopped {}", host_b),
+            "terminated worker".to_string()
+        ]
+    );
+}
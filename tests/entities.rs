@@ -0,0 +1,146 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use actors_rs::*;
+
+use actors_rs::system::SystemBuilder;
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+
+#[derive(Clone, Debug)]
+pub struct TestProbe(ChannelProbe<(), String>);
+
+#[derive(Clone, Debug)]
+pub struct Deposit(u32);
+
+#[actor(TestProbe, Deposit)]
+struct Account {
+    id: String,
+    balance: u32,
+    probe: Option<TestProbe>,
+}
+
+impl ActorFactoryArgs<String> for Account {
+    fn create_args(id: String) -> Self {
+        Account {
+            id,
+            balance: 0,
+            probe: None,
+        }
+    }
+}
+
+impl Actor for Account {
+    type Msg = AccountMsg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.receive(ctx, msg, sender);
+    }
+
+    fn post_stop(&mut self) {
+        if let Some(ref probe) = self.probe {
+            probe.0.event(format!("{} stopped", self.id));
+        }
+    }
+}
+
+impl Receive<TestProbe> for Account {
+    type Msg = AccountMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: TestProbe, _sender: Sender) {
+        msg.0.event(format!("{} received", self.id));
+        self.probe = Some(msg);
+    }
+}
+
+impl Receive<Deposit> for Account {
+    type Msg = AccountMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: Deposit, _sender: Sender) {
+        self.balance += msg.0;
+        if let Some(ref probe) = self.probe {
+            probe
+                .0
+                .event(format!("{} balance {}", self.id, self.balance));
+        }
+    }
+}
+
+#[test]
+fn entity_passivate_and_recreate() {
+    let mut cfg = load_config();
+    cfg.set("cqrs.sleep_after_secs", 1).unwrap();
+    let sys = SystemBuilder::new().cfg(cfg).create().unwrap();
+
+    let accounts = entity_manager::<Account>("accounts", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    accounts.tell(EntityMsg::new("acc-1", TestProbe(probe.clone())), None);
+    p_assert_eq!(listen, "acc-1 received".to_string());
+
+    // after a second without messages the entity actor is stopped
+    p_assert_eq!(listen, "acc-1 stopped".to_string());
+
+    // the next message creates the entity actor again
+    accounts.tell(EntityMsg::new("acc-1", TestProbe(probe)), None);
+    p_assert_eq!(listen, "acc-1 received".to_string());
+}
+
+#[test]
+fn entity_ids_are_encoded() {
+    let sys = ActorSystem::new().unwrap();
+
+    let accounts = entity_manager::<Account>("accounts", &sys).unwrap();
+
+    // ids that aren't valid actor names, and an id that is the
+    // encoded name of another id
+    let (probe, listen) = probe();
+    for id in &["acc/1", "acc 2", "acc_2f1", "ü", ""] {
+        accounts.tell(EntityMsg::new(id, TestProbe(probe.clone())), None);
+        p_assert_eq!(listen, format!("{} received", id));
+    }
+
+    accounts.tell(EntityMsg::new("acc/1", Deposit(5)), None);
+    p_assert_eq!(listen, "acc/1 balance 5".to_string());
+    accounts.tell(EntityMsg::new("acc_2f1", Deposit(7)), None);
+    p_assert_eq!(listen, "acc_2f1 balance 7".to_string());
+}
+
+#[test]
+fn entity_concurrent_entities() {
+    let sys = ActorSystem::new().unwrap();
+
+    let accounts = entity_manager::<Account>("accounts", &sys).unwrap();
+
+    let (probe, listen) = probe();
+    let ids: Vec<String> = (0..10).map(|i| format!("acc-{}", i)).collect();
+    for id in &ids {
+        accounts.tell(EntityMsg::new(id, TestProbe(probe.clone())), None);
+    }
+
+    // deposits to the entities are interleaved
+    for _ in 0..3 {
+        for id in &ids {
+            accounts.tell(EntityMsg::new(id, Deposit(1)), None);
+        }
+    }
+
+    // each entity has its own actor and state
+    let mut events: Vec<String> = (0..ids.len() * 4).map(|_| listen.recv()).collect();
+    events.sort();
+
+    let mut expected: Vec<String> = ids
+        .iter()
+        .flat_map(|id| {
+            vec![
+                format!("{} received", id),
+                format!("{} balance 1", id),
+                format!("{} balance 2", id),
+                format!("{} balance 3", id),
+            ]
+        })
+        .collect();
+    expected.sort();
+
+    assert_eq!(events, expected);
+}
@@ -0,0 +1,282 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+
+#[derive(Clone, Debug)]
+pub struct OrderPlaced(u32);
+
+#[derive(Clone, Debug)]
+pub struct OrderCancelled(u32);
+
+/// The group of order events
+#[derive(Clone, Debug)]
+pub enum OrderEvent {
+    Placed(OrderPlaced),
+    Cancelled(OrderCancelled),
+}
+
+impl From<OrderPlaced> for OrderEvent {
+    fn from(evt: OrderPlaced) -> Self {
+        OrderEvent::Placed(evt)
+    }
+}
+
+impl From<OrderCancelled> for OrderEvent {
+    fn from(evt: OrderCancelled) -> Self {
+        OrderEvent::Cancelled(evt)
+    }
+}
+
+/// Subscribes to placed orders
+#[actor(OrderPlaced)]
+struct Shipping {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Shipping {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Shipping { probe }
+    }
+}
+
+impl Actor for Shipping {
+    type Msg = ShippingMsg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system
+            .event_stream()
+            .subscribe::<OrderPlaced>(ctx.myself());
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.receive(ctx, msg, sender);
+    }
+}
+
+impl Receive<OrderPlaced> for Shipping {
+    type Msg = ShippingMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: OrderPlaced, _sender: Sender) {
+        self.probe.event(format!("shipping placed {}", msg.0));
+    }
+}
+
+/// Subscribes to every order event
+struct Audit {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Audit {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Audit { probe }
+    }
+}
+
+impl Actor for Audit {
+    type Msg = OrderEvent;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system
+            .event_stream()
+            .subscribe::<OrderEvent>(ctx.myself());
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        let evt = match msg {
+            OrderEvent::Placed(evt) => format!("audit placed {}", evt.0),
+            OrderEvent::Cancelled(evt) => format!("audit cancelled {}", evt.0),
+        };
+        self.probe.event(evt);
+    }
+}
+
+#[test]
+fn event_stream_publishes_by_type() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    let shipping = sys.actor_of_args::<Shipping, _>("shipping", probe).unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    // subscribing again has no effect
+    sys.event_stream().subscribe::<OrderPlaced>(shipping);
+
+    sys.event_stream().publish(OrderCancelled(1));
+    sys.event_stream().publish(OrderPlaced(2));
+    sys.event_stream().publish(OrderPlaced(3));
+
+    // the cancelled order has no subscriber
+    p_assert_eq!(listen, "shipping placed 2".to_string());
+    p_assert_eq!(listen, "shipping placed 3".to_string());
+}
+
+#[test]
+fn event_stream_publishes_to_groups() {
+    let sys = ActorSystem::new().unwrap();
+    let events = sys.event_stream();
+    events.register_group::<OrderPlaced, OrderEvent>();
+    events.register_group::<OrderCancelled, OrderEvent>();
+    // registering a group again has no effect
+    events.register_group::<OrderPlaced, OrderEvent>();
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<Shipping, _>("shipping", probe.clone())
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    sys.actor_of_args::<Audit, _>("audit", probe).unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    events.publish(OrderPlaced(1));
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(received, vec!["audit placed 1", "shipping placed 1"]);
+
+    events.publish(OrderCancelled(1));
+    p_assert_eq!(listen, "audit cancelled 1".to_string());
+}
+
+#[derive(Clone, Debug)]
+pub struct Ping(u32);
+
+#[derive(Clone, Debug)]
+pub struct Pong(u32);
+
+impl From<Ping> for Pong {
+    fn from(evt: Ping) -> Self {
+        Pong(evt.0)
+    }
+}
+
+impl From<Pong> for Ping {
+    fn from(evt: Pong) -> Self {
+        Ping(evt.0)
+    }
+}
+
+/// Subscribes to pings and pongs
+#[actor(Ping, Pong)]
+struct PingPong {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for PingPong {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        PingPong { probe }
+    }
+}
+
+impl Actor for PingPong {
+    type Msg = PingPongMsg;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system.event_stream().subscribe::<Ping>(ctx.myself());
+        ctx.system.event_stream().subscribe::<Pong>(ctx.myself());
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.receive(ctx, msg, sender);
+    }
+}
+
+impl Receive<Ping> for PingPong {
+    type Msg = PingPongMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: Ping, _sender: Sender) {
+        self.probe.event(format!("ping {}", msg.0));
+    }
+}
+
+impl Receive<Pong> for PingPong {
+    type Msg = PingPongMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: Pong, _sender: Sender) {
+        self.probe.event(format!("pong {}", msg.0));
+    }
+}
+
+#[test]
+fn event_stream_groups_are_not_nested() {
+    let sys = ActorSystem::new().unwrap();
+    let events = sys.event_stream();
+
+    // each type is a group of the other
+    events.register_group::<Ping, Pong>();
+    events.register_group::<Pong, Ping>();
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<PingPong, _>("ping-pong", probe)
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    // an event is sent to its groups once, and not on to their groups
+    events.publish(Ping(1));
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(received, vec!["ping 1", "pong 1"]);
+
+    events.publish(Pong(2));
+    let mut received = vec![listen.recv(), listen.recv()];
+    received.sort();
+    assert_eq!(received, vec!["ping 2", "pong 2"]);
+}
+
+struct DeadLetterReporter {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for DeadLetterReporter {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        DeadLetterReporter { probe }
+    }
+}
+
+impl Actor for DeadLetterReporter {
+    type Msg = DeadLetter;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system.dead_letters().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: All.into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".into());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(format!("dead letter {}", msg.msg));
+    }
+}
+
+#[test]
+fn event_stream_unsubscribes_terminated_actors() {
+    let sys = ActorSystem::new().unwrap();
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<DeadLetterReporter, _>("dl-reporter", probe.clone())
+        .unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+    let shipping = sys.actor_of_args::<Shipping, _>("shipping", probe).unwrap();
+    p_assert_eq!(listen, "subscribed".to_string());
+
+    // the subscriber stops without unsubscribing
+    sys.stop(&shipping);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // the event isn't sent to the stopped subscriber, so the first
+    // dead letter is the one sent directly
+    sys.event_stream().publish(OrderPlaced(1));
+    shipping.tell(OrderPlaced(2), None);
+    p_assert_eq!(
+        listen,
+        "dead letter OrderPlaced(OrderPlaced(2))".to_string()
+    );
+}
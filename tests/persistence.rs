@@ -0,0 +1,271 @@
+use actors_rs::system::{
+    ActorSystem, EventQuery, FsSnapshotStore, MemoryEventStore, PersistenceConf, Retention,
+    Snapshot,
+};
+use actors_rs::*;
+
+use futures::{executor::block_on, StreamExt};
+
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+
+#[derive(Clone, Debug)]
+pub struct Add(u32);
+
+#[derive(Clone, Debug)]
+pub struct TakeSnapshot;
+
+#[derive(Clone, Debug)]
+pub struct TestProbe(ChannelProbe<(), (u32, u32)>);
+
+#[actor(Add, TakeSnapshot, TestProbe)]
+struct Counter {
+    conf: PersistenceConf,
+    total: u32,
+    replayed: u32,
+}
+
+impl ActorFactoryArgs<PersistenceConf> for Counter {
+    fn create_args(conf: PersistenceConf) -> Self {
+        Counter {
+            conf,
+            total: 0,
+            replayed: 0,
+        }
+    }
+}
+
+impl Actor for Counter {
+    type Msg = CounterMsg;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Sender) {
+        self.receive(ctx, msg, sender);
+    }
+
+    fn persistence_conf(&self) -> Option<PersistenceConf> {
+        Some(self.conf.clone())
+    }
+
+    fn recover_snapshot(&mut self, _ctx: &Context<Self::Msg>, snapshot: Snapshot) {
+        self.total = snapshot.state().unwrap();
+    }
+
+    fn replay_event(&mut self, _ctx: &Context<Self::Msg>, evt: Self::Msg) {
+        if let CounterMsg::Add(Add(n)) = evt {
+            self.total += n;
+            self.replayed += 1;
+        }
+    }
+}
+
+impl Receive<Add> for Counter {
+    type Msg = CounterMsg;
+
+    fn receive(&mut self, ctx: &Context<Self::Msg>, msg: Add, _sender: Sender) {
+        let n = msg.0;
+        ctx.persist_tagged(msg.into(), &["adds"]).unwrap();
+        self.total += n;
+    }
+}
+
+impl Receive<TakeSnapshot> for Counter {
+    type Msg = CounterMsg;
+
+    fn receive(&mut self, ctx: &Context<Self::Msg>, _msg: TakeSnapshot, _sender: Sender) {
+        ctx.save_snapshot(&self.total).unwrap();
+    }
+}
+
+impl Receive<TestProbe> for Counter {
+    type Msg = CounterMsg;
+
+    fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: TestProbe, _sender: Sender) {
+        msg.0.event((self.total, self.replayed));
+    }
+}
+
+fn persistent_system(dir: &str) -> ActorSystem {
+    let dir = std::env::temp_dir().join(format!("{}-{}", dir, uuid::Uuid::new_v4()));
+    let sys = ActorSystem::new().unwrap();
+    sys.set_event_store(MemoryEventStore::<CounterMsg>::default());
+    sys.set_snapshot_store(FsSnapshotStore::new(dir));
+    sys
+}
+
+fn counter_state(counter: &ActorRef<CounterMsg>) -> (u32, u32) {
+    let (probe, listen) = probe();
+    counter.tell(TestProbe(probe), None);
+    listen.recv()
+}
+
+#[test]
+fn persist_recover_from_snapshot() {
+    let sys = persistent_system("persist_recover");
+    let conf = PersistenceConf::new("counter-1", "counters");
+
+    let counter = sys
+        .actor_of_args::<Counter, _>("counter-a", conf.clone())
+        .unwrap();
+    counter.tell(Add(1), None);
+    counter.tell(Add(2), None);
+    counter.tell(TakeSnapshot, None);
+    counter.tell(Add(3), None);
+
+    assert_eq!(counter_state(&counter), (6, 0));
+
+    // a new actor with the same persistence id recovers from the
+    // snapshot and replays only the event persisted after it
+    let recovered = sys.actor_of_args::<Counter, _>("counter-b", conf).unwrap();
+
+    assert_eq!(counter_state(&recovered), (6, 1));
+}
+
+#[test]
+fn persist_snapshot_retention() {
+    let sys = persistent_system("persist_retention");
+    let mut conf = PersistenceConf::new("counter-2", "counters");
+    conf.retention = Retention {
+        keep_snapshots: Some(1),
+        delete_events: true,
+    };
+
+    let counter = sys.actor_of_args::<Counter, _>("counter", conf).unwrap();
+    counter.tell(Add(1), None);
+    counter.tell(TakeSnapshot, None);
+    counter.tell(Add(2), None);
+    counter.tell(TakeSnapshot, None);
+    counter.tell(Add(3), None);
+
+    assert_eq!(counter_state(&counter), (6, 0));
+
+    let snapshots = sys
+        .snapshot_store()
+        .unwrap()
+        .list("counter-2", "counters")
+        .unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].seq_nr, 2);
+
+    let events = sys
+        .event_store::<CounterMsg>()
+        .unwrap()
+        .load("counter-2", "counters");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].seq_nr, 3);
+}
+
+#[test]
+fn persist_snapshot_retention_same_seq_nr() {
+    let sys = persistent_system("persist_retention_same_seq_nr");
+    let mut conf = PersistenceConf::new("counter-3", "counters");
+    conf.retention = Retention {
+        keep_snapshots: Some(1),
+        delete_events: true,
+    };
+
+    let counter = sys
+        .actor_of_args::<Counter, _>("counter-a", conf.clone())
+        .unwrap();
+    counter.tell(Add(1), None);
+    counter.tell(Add(2), None);
+    counter.tell(TakeSnapshot, None);
+    assert_eq!(counter_state(&counter), (3, 0));
+
+    // a second snapshot without new events has the same seq_nr
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    counter.tell(TakeSnapshot, None);
+    assert_eq!(counter_state(&counter), (3, 0));
+
+    let snapshots = sys
+        .snapshot_store()
+        .unwrap()
+        .list("counter-3", "counters")
+        .unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].seq_nr, 2);
+
+    // the events are deleted, so the state is recovered from the snapshot kept
+    let recovered = sys.actor_of_args::<Counter, _>("counter-b", conf).unwrap();
+    assert_eq!(counter_state(&recovered), (3, 0));
+}
+
+#[test]
+fn persist_delete_events_keeps_snapshots() {
+    let sys = persistent_system("persist_delete_events");
+    let mut conf = PersistenceConf::new("counter-4", "counters");
+    conf.retention = Retention {
+        keep_snapshots: None,
+        delete_events: true,
+    };
+
+    let counter = sys.actor_of_args::<Counter, _>("counter", conf).unwrap();
+    counter.tell(Add(1), None);
+    counter.tell(TakeSnapshot, None);
+    counter.tell(Add(2), None);
+    counter.tell(TakeSnapshot, None);
+    counter.tell(Add(3), None);
+    assert_eq!(counter_state(&counter), (6, 0));
+
+    let snapshots = sys
+        .snapshot_store()
+        .unwrap()
+        .list("counter-4", "counters")
+        .unwrap();
+    assert_eq!(snapshots.len(), 2);
+
+    // only the events covered by the oldest snapshot are deleted
+    let events = sys
+        .event_store::<CounterMsg>()
+        .unwrap()
+        .load("counter-4", "counters");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].seq_nr, 2);
+}
+
+fn added(sys: &ActorSystem, tag: &str, from_offset: u64, n: usize) -> Vec<(u64, String, u32)> {
+    let events = sys.events_by_tag::<CounterMsg>(tag, from_offset).take(n);
+    block_on(events.collect::<Vec<_>>())
+        .into_iter()
+        .map(|e| match e.evt.msg {
+            CounterMsg::Add(Add(n)) => (e.offset, e.id, n),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[test]
+fn persist_query_events_by_tag() {
+    let sys = persistent_system("persist_query");
+
+    let a = sys
+        .actor_of_args::<Counter, _>("a", PersistenceConf::new("a", "counters"))
+        .unwrap();
+    let b = sys
+        .actor_of_args::<Counter, _>("b", PersistenceConf::new("b", "counters"))
+        .unwrap();
+    a.tell(Add(1), None);
+    counter_state(&a);
+    b.tell(Add(2), None);
+    counter_state(&b);
+
+    // the stream yields the persisted events and then continues
+    // with events persisted after the query started
+    let live = sys.events_by_tag::<CounterMsg>("adds", 0).take(3);
+    a.tell(Add(3), None);
+    let offsets: Vec<u64> = block_on(live.map(|e| e.offset).collect());
+    assert_eq!(offsets, vec![1, 2, 3]);
+
+    // a query can resume from an offset
+    assert_eq!(
+        added(&sys, "adds", 2, 2),
+        vec![(2, "b".to_string(), 2), (3, "a".to_string(), 3)]
+    );
+
+    let by_id: Vec<u64> = block_on(
+        sys.events_by_id::<CounterMsg>("a", "counters", 2)
+            .take(1)
+            .map(|e| e.evt.seq_nr)
+            .collect(),
+    );
+    assert_eq!(by_id, vec![2]);
+}
@@ -0,0 +1,209 @@
+extern crate riker_testkit;
+
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors_rs::cluster::{Cluster, DistributedPubSub};
+use actors_rs::remote::Remoting;
+use actors_rs::system::{JsonCodec, SystemBuilder};
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe, ChannelProbeReceive};
+use riker_testkit::probe::{Probe, ProbeReceive};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Order(u32);
+
+type Subscription = (ChannelRef<Order>, Option<String>, ChannelProbe<(), String>);
+
+/// Subscribes to the "orders" topic and reports the orders it receives
+/// with the node it runs on
+struct Subscriber {
+    chan: ChannelRef<Order>,
+    group: Option<String>,
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<Subscription> for Subscriber {
+    fn create_args((chan, group, probe): Subscription) -> Self {
+        Subscriber { chan, group, probe }
+    }
+}
+
+impl Actor for Subscriber {
+    type Msg = Order;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let actor = Box::new(ctx.myself());
+        let topic = Topic::from("orders");
+
+        match self.group.clone() {
+            Some(group) => self.chan.tell(
+                SubscribeGroup {
+                    actor,
+                    topic,
+                    group,
+                },
+                None,
+            ),
+            None => self.chan.tell(Subscribe { actor, topic }, None),
+        }
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(format!("{} {}", msg.0, ctx.system.host()));
+    }
+}
+
+fn node(seeds: &[SocketAddr]) -> (ActorSystem, Cluster, ChannelRef<Order>) {
+    let mut cfg = load_config();
+    cfg.set("cluster.heartbeat_interval_millis", 100).unwrap();
+    cfg.set("cluster.gossip_interval_millis", 100).unwrap();
+    cfg.set("pubsub.gossip_interval_millis", 100).unwrap();
+
+    let sys = SystemBuilder::new()
+        .name("pubsub")
+        .cfg(cfg)
+        .create()
+        .unwrap();
+    sys.serialization()
+        .register::<Order, _>("test.Order", JsonCodec)
+        .unwrap();
+
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+    let cluster = Cluster::join(&remote, seeds).unwrap();
+    let chan = DistributedPubSub::new(&cluster)
+        .unwrap()
+        .channel::<Order>("orders")
+        .unwrap();
+    (sys, cluster, chan)
+}
+
+fn await_members(cluster: &Cluster, expected: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while cluster.members().len() != expected {
+        assert!(Instant::now() < deadline, "cluster did not converge");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn subscribe(
+    sys: &ActorSystem,
+    chan: &ChannelRef<Order>,
+    group: Option<&str>,
+    probe: ChannelProbe<(), String>,
+) -> ActorRef<Order> {
+    subscribe_as(sys, "subscriber", chan, group, probe)
+}
+
+fn subscribe_as(
+    sys: &ActorSystem,
+    name: &str,
+    chan: &ChannelRef<Order>,
+    group: Option<&str>,
+    probe: ChannelProbe<(), String>,
+) -> ActorRef<Order> {
+    let group = group.map(String::from);
+    sys.actor_of_args::<Subscriber, _>(name, (chan.clone(), group, probe))
+        .unwrap()
+}
+
+fn publish(chan: &ChannelRef<Order>, order: u32) {
+    chan.tell(
+        Publish {
+            msg: Order(order),
+            topic: "orders".into(),
+        },
+        None,
+    );
+}
+
+fn received(listen: &ChannelProbeReceive<String>, count: usize) -> Vec<String> {
+    let mut events: Vec<String> = (0..count).map(|_| listen.recv()).collect();
+    events.sort();
+    events
+}
+
+#[test]
+fn pubsub_publishes_to_every_node() {
+    let (sys_a, cluster_a, chan_a) = node(&[]);
+    let (sys_b, cluster_b, chan_b) = node(&[cluster_a.self_addr()]);
+    let (_sys_c, cluster_c, chan_c) = node(&[cluster_a.self_addr()]);
+    await_members(&cluster_a, 3);
+    await_members(&cluster_c, 3);
+
+    let (probe, listen) = probe();
+    subscribe(&sys_a, &chan_a, None, probe.clone());
+    subscribe(&sys_b, &chan_b, None, probe);
+
+    // wait for the subscriptions to be replicated
+    thread::sleep(Duration::from_millis(500));
+
+    // a message published on any node reaches the subscribers on every node
+    let node_a = cluster_a.self_addr();
+    let node_b = cluster_b.self_addr();
+    for (i, chan) in [&chan_a, &chan_b, &chan_c].iter().enumerate() {
+        publish(chan, i as u32);
+
+        let mut expected = vec![format!("{} {}", i, node_a), format!("{} {}", i, node_b)];
+        expected.sort();
+        assert_eq!(received(&listen, 2), expected);
+    }
+}
+
+#[test]
+fn pubsub_sends_to_one_member_of_group() {
+    let (sys_a, cluster_a, chan_a) = node(&[]);
+    let (sys_b, cluster_b, chan_b) = node(&[cluster_a.self_addr()]);
+    let (_sys_c, cluster_c, chan_c) = node(&[cluster_a.self_addr()]);
+    await_members(&cluster_a, 3);
+    await_members(&cluster_c, 3);
+
+    let (probe, listen) = probe();
+    subscribe(&sys_a, &chan_a, Some("workers"), probe.clone());
+    subscribe(&sys_b, &chan_b, Some("workers"), probe);
+
+    // wait for the subscriptions to be replicated
+    thread::sleep(Duration::from_millis(500));
+
+    // each order is received by one worker, in turn
+    for i in 0..4 {
+        publish(&chan_c, i);
+    }
+
+    let node_a = cluster_a.self_addr().to_string();
+    let node_b = cluster_b.self_addr().to_string();
+    let events = received(&listen, 4);
+    assert_eq!(events.iter().filter(|e| e.ends_with(&node_a)).count(), 2);
+    assert_eq!(events.iter().filter(|e| e.ends_with(&node_b)).count(), 2);
+}
+
+#[test]
+fn pubsub_removes_terminated_subscribers() {
+    let (sys_a, cluster_a, chan_a) = node(&[]);
+    let (_sys_b, cluster_b, chan_b) = node(&[cluster_a.self_addr()]);
+    await_members(&cluster_a, 2);
+    await_members(&cluster_b, 2);
+
+    let (probe, listen) = probe();
+    let worker_1 = subscribe_as(&sys_a, "worker-1", &chan_a, Some("workers"), probe.clone());
+    subscribe_as(&sys_a, "worker-2", &chan_a, Some("workers"), probe);
+
+    // wait for the subscriptions to be replicated
+    thread::sleep(Duration::from_millis(500));
+
+    // a subscriber that stops without unsubscribing is removed from
+    // its group, so the remaining member receives every order
+    sys_a.stop(&worker_1);
+    thread::sleep(Duration::from_millis(200));
+
+    for i in 0..4 {
+        publish(&chan_b, i);
+    }
+
+    let node_a = cluster_a.self_addr();
+    let expected: Vec<_> = (0..4).map(|i| format!("{} {}", i, node_a)).collect();
+    assert_eq!(received(&listen, 4), expected);
+}
@@ -0,0 +1,291 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+
+use actors_rs::remote::Remoting;
+use actors_rs::system::{JsonCodec, SystemBuilder};
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ping(String);
+
+struct Recorder {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Recorder {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Recorder { probe }
+    }
+}
+
+impl Actor for Recorder {
+    type Msg = Ping;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg.0);
+    }
+}
+
+struct DeadLetterSub {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for DeadLetterSub {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        DeadLetterSub { probe }
+    }
+}
+
+impl Actor for DeadLetterSub {
+    type Msg = DeadLetter;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        ctx.system.dead_letters().tell(
+            Subscribe {
+                actor: Box::new(ctx.myself()),
+                topic: "*".into(),
+            },
+            None,
+        );
+        self.probe.event("subscribed".to_string());
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg.msg);
+    }
+}
+
+fn remote_system(name: &str, reconnect_attempts: i64) -> ActorSystem {
+    let mut cfg = load_config();
+    cfg.set("remote.reconnect_interval_millis", 100).unwrap();
+    cfg.set("remote.reconnect_attempts", reconnect_attempts)
+        .unwrap();
+
+    let sys = SystemBuilder::new().name(name).cfg(cfg).create().unwrap();
+    sys.serialization()
+        .register::<Ping, _>("test.Ping", JsonCodec)
+        .unwrap();
+    sys
+}
+
+fn address(system: &str, addr: SocketAddr, path: &str) -> ActorAddress {
+    format!("riker://{}@{}{}", system, addr, path)
+        .parse()
+        .unwrap()
+}
+
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn remote_tell() {
+    let sys_a = remote_system("a", 10);
+    let sys_b = remote_system("b", 10);
+    let remote_a = Remoting::start(&sys_a, "127.0.0.1:0").unwrap();
+    let remote_b = Remoting::start(&sys_b, "127.0.0.1:0").unwrap();
+
+    let (probe, listen) = probe();
+    sys_b
+        .actor_of_args::<Recorder, _>("recorder", probe)
+        .unwrap();
+
+    let addr = address("b", remote_b.local_addr(), "/user/recorder");
+    let recorder = remote_a.actor_ref::<Ping>(&addr).unwrap();
+    assert!(recorder.cell.is_remote());
+    assert_eq!(recorder.address(), addr);
+    assert_eq!(*recorder.uri().host, remote_b.local_addr().to_string());
+
+    recorder.tell(Ping("hello".to_string()), None);
+    recorder.tell(Ping("world".to_string()), None);
+    p_assert_eq!(listen, "hello".to_string());
+    p_assert_eq!(listen, "world".to_string());
+
+    // the reference is created once per address
+    let proxies = || {
+        let daemon = sys_a.sys_root().children().find(|a| a.name() == "remoting");
+        daemon.unwrap().children().count()
+    };
+    let count = proxies();
+    assert_eq!(count, 1);
+    for _ in 0..10 {
+        let again = remote_a.actor_ref::<Ping>(&addr).unwrap();
+        assert_eq!(again.path(), recorder.path());
+    }
+    assert_eq!(proxies(), count);
+}
+
+#[test]
+fn remote_reconnect() {
+    let sys_a = remote_system("a", 50);
+    let sys_b = remote_system("b", 10);
+    let remote_a = Remoting::start(&sys_a, "127.0.0.1:0").unwrap();
+
+    let (probe, listen) = probe();
+    sys_b
+        .actor_of_args::<Recorder, _>("recorder", probe)
+        .unwrap();
+
+    // the message is sent before the remote system accepts connections
+    let addr = free_addr();
+    let recorder = remote_a
+        .actor_ref::<Ping>(&address("b", addr, "/user/recorder"))
+        .unwrap();
+    recorder.tell(Ping("hello".to_string()), None);
+
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    Remoting::start(&sys_b, addr).unwrap();
+
+    p_assert_eq!(listen, "hello".to_string());
+}
+
+#[test]
+fn remote_dead_letters() {
+    let sys_a = remote_system("a", 1);
+    let sys_b = remote_system("b", 10);
+    let remote_a = Remoting::start(&sys_a, "127.0.0.1:0").unwrap();
+    let remote_b = Remoting::start(&sys_b, "127.0.0.1:0").unwrap();
+
+    let (probe_a, listen_a) = probe();
+    sys_a
+        .actor_of_args::<DeadLetterSub, _>("dl-subscriber", probe_a)
+        .unwrap();
+    p_assert_eq!(listen_a, "subscribed".to_string());
+
+    let (probe_b, listen_b) = probe();
+    sys_b
+        .actor_of_args::<DeadLetterSub, _>("dl-subscriber", probe_b)
+        .unwrap();
+    p_assert_eq!(listen_b, "subscribed".to_string());
+
+    // the recipient doesn't exist on the remote system
+    let missing = remote_a
+        .actor_ref::<Ping>(&address("b", remote_b.local_addr(), "/user/missing"))
+        .unwrap();
+    missing.tell(Ping("hello".to_string()), None);
+    p_assert_eq!(
+        listen_b,
+        "test.Ping for unknown actor /user/missing".to_string()
+    );
+
+    // the remote system is unreachable
+    let unreachable = remote_a
+        .actor_ref::<Ping>(&address("b", free_addr(), "/user/recorder"))
+        .unwrap();
+    unreachable.tell(Ping("lost".to_string()), None);
+    p_assert_eq!(listen_a, "Ping(\"lost\")".to_string());
+}
+
+#[test]
+fn remote_unreachable_system() {
+    let sys_a = remote_system("a", 2);
+    let sys_b = remote_system("b", 10);
+    let remote_a = Remoting::start(&sys_a, "127.0.0.1:0").unwrap();
+
+    let (probe_a, listen_a) = probe();
+    sys_a
+        .actor_of_args::<DeadLetterSub, _>("dl-subscriber", probe_a)
+        .unwrap();
+    p_assert_eq!(listen_a, "subscribed".to_string());
+
+    let addr = free_addr();
+    let recorder = remote_a
+        .actor_ref::<Ping>(&address("b", addr, "/user/recorder"))
+        .unwrap();
+    recorder.tell(Ping("lost".to_string()), None);
+    p_assert_eq!(listen_a, "Ping(\"lost\")".to_string());
+
+    // once the system is unreachable, messages aren't retried
+    let start = std::time::Instant::now();
+    for i in 0..5 {
+        recorder.tell(Ping(format!("lost-{}", i)), None);
+    }
+    for i in 0..5 {
+        p_assert_eq!(listen_a, format!("Ping(\"lost-{}\")", i));
+    }
+    assert!(start.elapsed() < std::time::Duration::from_millis(200));
+
+    // until it's reconnected to
+    let (probe_b, listen_b) = probe();
+    sys_b
+        .actor_of_args::<Recorder, _>("recorder", probe_b)
+        .unwrap();
+    Remoting::start(&sys_b, addr).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    recorder.tell(Ping("hello".to_string()), None);
+    p_assert_eq!(listen_b, "hello".to_string());
+}
+
+#[test]
+fn remote_local_address() {
+    let sys = remote_system("a", 10);
+    let (probe, _listen) = probe();
+    let recorder = sys.actor_of_args::<Recorder, _>("recorder", probe).unwrap();
+    assert_eq!(
+        recorder.address().to_string(),
+        "riker://a@localhost/user/recorder"
+    );
+
+    // starting remoting makes the remoting address the system's host
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+    assert_eq!(
+        recorder.address(),
+        address("a", remote.local_addr(), "/user/recorder")
+    );
+}
+
+#[test]
+fn remote_oversized_frames() {
+    let sys = remote_system("a", 10);
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+
+    // a peer announcing a frame larger than remote.max_frame_bytes is
+    // disconnected before the frame is read
+    let mut stream = std::net::TcpStream::connect(remote.local_addr()).unwrap();
+    stream.write_all(&u32::MAX.to_be_bytes()).unwrap();
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut buf = [0; 1];
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+
+    // oversized messages aren't sent
+    let mut cfg = load_config();
+    cfg.set("remote.max_frame_bytes", 100).unwrap();
+    let sys_a = SystemBuilder::new().name("a").cfg(cfg).create().unwrap();
+    sys_a
+        .serialization()
+        .register::<Ping, _>("test.Ping", JsonCodec)
+        .unwrap();
+    let remote_a = Remoting::start(&sys_a, "127.0.0.1:0").unwrap();
+
+    let (probe_a, listen_a) = probe();
+    sys_a
+        .actor_of_args::<DeadLetterSub, _>("dl-subscriber", probe_a)
+        .unwrap();
+    p_assert_eq!(listen_a, "subscribed".to_string());
+
+    let (probe, listen) = probe();
+    sys.actor_of_args::<Recorder, _>("recorder", probe).unwrap();
+    let recorder = remote_a
+        .actor_ref::<Ping>(&address("a", remote.local_addr(), "/user/recorder"))
+        .unwrap();
+
+    let large = "x".repeat(200);
+    recorder.tell(Ping(large.clone()), None);
+    p_assert_eq!(listen_a, format!("Ping({:?})", large));
+
+    recorder.tell(Ping("small".to_string()), None);
+    p_assert_eq!(listen, "small".to_string());
+}
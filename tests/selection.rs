@@ -180,6 +180,206 @@ fn select_paths() {
     assert!(sys.select("!").is_err());
     assert!(sys.select("foo/$").is_err());
     assert!(sys.select("&").is_err());
+
+    assert!(sys.select("/foo/**").is_ok());
+    assert!(sys.select("foo/**/bar-*").is_ok());
+    assert!(sys.select("foo/bar-?").is_ok());
+    assert!(sys.select("foo/bar-[0-9]").is_ok());
+    assert!(sys.select("foo/[!a-c]*").is_ok());
+
+    assert!(sys.select("foo/bar-[").is_err());
+    assert!(sys.select("foo/bar-]").is_err());
+    assert!(sys.select("foo/bar-[]").is_err());
+}
+
+#[test]
+fn select_address() {
+    let sys = ActorSystem::with_name("my-app").unwrap();
+
+    let actor = sys.actor_of::<SelectTest>("select-actor").unwrap();
+    let address = actor.address();
+    assert_eq!(
+        address.to_string(),
+        "riker://my-app@localhost/user/select-actor"
+    );
+    assert_eq!(
+        address.to_string().parse::<ActorAddress>().unwrap(),
+        address
+    );
+
+    let (probe, listen) = probe();
+    let sel = sys.select(&address.to_string()).unwrap();
+    sel.try_tell(TestProbe(probe), None);
+    p_assert_eq!(listen, ());
+
+    // addresses of other actor systems can't be selected
+    assert!(sys
+        .select("riker://other-app@localhost/user/select-actor")
+        .is_err());
+    assert!(sys
+        .select("riker://my-app@10.0.0.1:7000/user/select-actor")
+        .is_err());
+}
+
+#[test]
+fn parse_address() {
+    let address: ActorAddress = "riker://my-app@10.0.0.1:7000/user/a/b".parse().unwrap();
+    assert_eq!(address.system, "my-app");
+    assert_eq!(address.host, "10.0.0.1");
+    assert_eq!(address.port, Some(7000));
+    assert_eq!(address.path, *"/user/a/b");
+    assert_eq!(address.to_string(), "riker://my-app@10.0.0.1:7000/user/a/b");
+
+    let address: ActorAddress = "riker://my-app@[::1]:7000/user/a".parse().unwrap();
+    assert_eq!(address.host, "[::1]");
+    assert_eq!(address.port, Some(7000));
+
+    for invalid in &[
+        "my-app@localhost/user/a",
+        "riker://my-app@localhost",
+        "riker://localhost/user/a",
+        "riker://my app@localhost/user/a",
+        "riker://my-app@/user/a",
+        "riker://my-app@localhost:port/user/a",
+        "riker://my-app@localhost/user/a b",
+        "riker://my-app@localhost/user/",
+        "riker://my-app@localhost/user/*",
+        "riker://my-app@localhost/user/**",
+        "riker://my-app@localhost/user/a?",
+        "riker://my-app@localhost/user/[ab]",
+        "riker://my-app@localhost/user/../a",
+    ] {
+        assert!(invalid.parse::<ActorAddress>().is_err(), "{}", invalid);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReportName(ChannelProbe<(), String>);
+
+/// Creates the actors at the given paths below it. Every actor reports
+/// its name when sent a `ReportName`.
+struct Tree {
+    paths: Vec<String>,
+}
+
+impl ActorFactoryArgs<Vec<String>> for Tree {
+    fn create_args(paths: Vec<String>) -> Self {
+        Tree { paths }
+    }
+}
+
+impl Actor for Tree {
+    type Msg = ReportName;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let mut children: Vec<(String, Vec<String>)> = Vec::new();
+        for path in &self.paths {
+            let mut segments = path.splitn(2, '/');
+            let name = segments.next().unwrap().to_string();
+            let rest: Vec<String> = segments.map(String::from).collect();
+
+            match children.iter_mut().find(|(child, _)| *child == name) {
+                Some((_, paths)) => paths.extend(rest),
+                None => children.push((name, rest)),
+            }
+        }
+
+        for (name, paths) in children {
+            ctx.actor_of_args::<Tree, _>(&name, paths).unwrap();
+        }
+    }
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        msg.0.event(ctx.myself().name().to_string());
+    }
+}
+
+#[test]
+fn select_patterns() {
+    let sys = ActorSystem::new().unwrap();
+
+    let paths = [
+        "eu/conn-1",
+        "eu/conn-2",
+        "eu/admin",
+        "us/conn-3",
+        "us/db/conn-4",
+    ];
+    let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+    sys.actor_of_args::<Tree, _>("servers", paths).unwrap();
+
+    // delay to allow the actors to be created
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let (probe, listen) = probe();
+    let selected = |path: &str, count: usize| {
+        sys.select(path)
+            .unwrap()
+            .try_tell(ReportName(probe.clone()), None);
+
+        let mut names: Vec<String> = (0..count).map(|_| listen.recv()).collect();
+        names.sort();
+        names
+    };
+
+    // `**` selects actors at any depth
+    assert_eq!(
+        selected("/user/servers/**/conn-*", 4),
+        vec!["conn-1", "conn-2", "conn-3", "conn-4"]
+    );
+    assert_eq!(
+        selected("servers/**", 9),
+        vec!["admin", "conn-1", "conn-2", "conn-3", "conn-4", "db", "eu", "servers", "us"]
+    );
+
+    // globs select the children with a matching name
+    assert_eq!(selected("servers/*/conn-[13]", 2), vec!["conn-1", "conn-3"]);
+    assert_eq!(selected("servers/eu/conn-?", 2), vec!["conn-1", "conn-2"]);
+    assert_eq!(selected("servers/eu/[!c]*", 1), vec!["admin"]);
+    assert_eq!(selected("servers/u?/d*/*", 1), vec!["conn-4"]);
+}
+
+#[test]
+fn select_resolve() {
+    let sys = ActorSystem::new().unwrap();
+
+    let paths = vec!["eu/conn-1".to_string(), "eu/admin".to_string()];
+    sys.actor_of_args::<Tree, _>("servers", paths).unwrap();
+
+    // delay to allow the actors to be created
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let mut names: Vec<String> = sys
+        .select("servers/eu/*")
+        .unwrap()
+        .resolve()
+        .iter()
+        .map(|actor| actor.name().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["admin", "conn-1"]);
+
+    let admin = sys.select("servers/eu/admin").unwrap().resolve_one();
+    assert_eq!(admin.unwrap().path().to_string(), "/user/servers/eu/admin");
+
+    let missing = sys.select("servers/us/*").unwrap();
+    assert!(missing.resolve().is_empty());
+    assert!(matches!(
+        missing.resolve_one(),
+        Err(ResolveError::NotFound(_))
+    ));
+
+    let conn = sys.select("servers/*/conn-1").unwrap();
+    assert!(matches!(
+        conn.resolve_one_typed::<String>(),
+        Err(ResolveError::UnexpectedType(_))
+    ));
+
+    // the typed ref can be kept and told directly
+    let conn: ActorRef<ReportName> = conn.resolve_one_typed().unwrap();
+    let (probe, listen) = probe();
+    conn.tell(ReportName(probe), None);
+    p_assert_eq!(listen, "conn-1".to_string());
 }
 
 // // *** Dead letters test ***
@@ -0,0 +1,94 @@
+use actors_rs::system::{ActorSystem, JsonCodec, SerializeError};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Deposit {
+    account: String,
+    amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Withdraw(u64);
+
+fn deposit() -> Deposit {
+    Deposit {
+        account: "acc-1".to_string(),
+        amount: 100,
+    }
+}
+
+#[test]
+fn serialization_round_trip() {
+    let sys = ActorSystem::new().unwrap();
+    let registry = sys.serialization();
+    registry
+        .register::<Deposit, _>("bank.Deposit", JsonCodec)
+        .unwrap();
+
+    let msg = registry.serialize(&deposit()).unwrap();
+    assert_eq!(msg.manifest, "bank.Deposit");
+
+    // a registry with the same registration deserializes the message
+    let other = ActorSystem::new().unwrap();
+    other
+        .serialization()
+        .register::<Deposit, _>("bank.Deposit", JsonCodec)
+        .unwrap();
+    assert_eq!(
+        other.serialization().deserialize::<Deposit>(&msg).unwrap(),
+        deposit()
+    );
+
+    let any = other.serialization().deserialize_any(&msg).unwrap();
+    assert_eq!(any.downcast_ref::<Deposit>(), Some(&deposit()));
+}
+
+#[test]
+fn serialization_errors() {
+    let sys = ActorSystem::new().unwrap();
+    let registry = sys.serialization();
+    registry
+        .register::<Deposit, _>("bank.Deposit", JsonCodec)
+        .unwrap();
+
+    assert!(matches!(
+        registry.serialize(&Withdraw(5)),
+        Err(SerializeError::NotRegistered(_))
+    ));
+    assert_eq!(
+        registry.register::<Withdraw, _>("bank.Deposit", JsonCodec),
+        Err(SerializeError::DuplicateManifest("bank.Deposit".to_string()))
+    );
+    assert!(matches!(
+        registry.register::<Deposit, _>("bank.Deposit.v2", JsonCodec),
+        Err(SerializeError::DuplicateType(_, _))
+    ));
+
+    let msg = registry.serialize(&deposit()).unwrap();
+    assert!(matches!(
+        registry.deserialize::<Withdraw>(&msg),
+        Err(SerializeError::WrongType(_, _))
+    ));
+
+    let other = ActorSystem::new().unwrap();
+    assert_eq!(
+        other.serialization().deserialize::<Deposit>(&msg),
+        Err(SerializeError::UnknownManifest("bank.Deposit".to_string()))
+    );
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn serialization_binary_codec() {
+    use actors_rs::system::BinaryCodec;
+
+    let sys = ActorSystem::new().unwrap();
+    let registry = sys.serialization();
+    registry
+        .register::<Deposit, _>("bank.Deposit", BinaryCodec)
+        .unwrap();
+
+    let msg = registry.serialize(&deposit()).unwrap();
+    assert_eq!(registry.deserialize::<Deposit>(&msg).unwrap(), deposit());
+}
@@ -0,0 +1,207 @@
+extern crate riker_testkit;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors_rs::cluster::{Cluster, ClusterSharding, ShardRegionMsg, ShardRegionRef};
+use actors_rs::remote::Remoting;
+use actors_rs::system::{JsonCodec, SystemBuilder};
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe, ChannelProbeReceive};
+use riker_testkit::probe::{Probe, ProbeReceive};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Incr;
+
+struct Counter {
+    id: String,
+}
+
+impl ActorFactoryArgs<String> for Counter {
+    fn create_args(id: String) -> Self {
+        Counter { id }
+    }
+}
+
+impl Actor for Counter {
+    type Msg = Incr;
+
+    fn recv(&mut self, ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {
+        // report the node the entity runs on
+        let evt = format!("{} {}", self.id, ctx.system.host());
+        ctx.select("/user/recorder").unwrap().try_tell(evt, None);
+    }
+}
+
+struct Recorder {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Recorder {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Recorder { probe }
+    }
+}
+
+impl Actor for Recorder {
+    type Msg = String;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+fn node(seeds: &[SocketAddr]) -> (ActorSystem, Cluster) {
+    let mut cfg = load_config();
+    cfg.set("cluster.heartbeat_interval_millis", 100).unwrap();
+    cfg.set("cluster.gossip_interval_millis", 100).unwrap();
+    cfg.set("sharding.number_of_shards", 10).unwrap();
+    // longer than the tests take, so that a shard that isn't handed off
+    // fails the test
+    cfg.set("sharding.hand_off_timeout_millis", 10_000).unwrap();
+
+    let sys = SystemBuilder::new()
+        .name("sharding")
+        .cfg(cfg)
+        .create()
+        .unwrap();
+    sys.serialization()
+        .register::<Incr, _>("test.Incr", JsonCodec)
+        .unwrap();
+
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+    let cluster = Cluster::join(&remote, seeds).unwrap();
+    (sys, cluster)
+}
+
+fn start_region(
+    sys: &ActorSystem,
+    cluster: &Cluster,
+    probe: ChannelProbe<(), String>,
+) -> ShardRegionRef<Incr> {
+    sys.actor_of_args::<Recorder, _>("recorder", probe).unwrap();
+    let sharding = ClusterSharding::new(cluster).unwrap();
+    sharding.start::<Counter>("counters").unwrap()
+}
+
+fn await_members(cluster: &Cluster, expected: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while cluster.members().len() != expected {
+        assert!(Instant::now() < deadline, "cluster did not converge");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Send a message to each entity and return the node each entity ran on
+fn incr_all(
+    region: &ShardRegionRef<Incr>,
+    listen: &ChannelProbeReceive<String>,
+) -> HashMap<String, String> {
+    for i in 0..20 {
+        region.tell(EntityMsg::new(&format!("counter-{}", i), Incr), None);
+    }
+
+    (0..20)
+        .map(|_| {
+            let evt = listen.recv();
+            let mut parts = evt.split(' ');
+            let id = parts.next().unwrap().to_string();
+            (id, parts.next().unwrap().to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn sharding_distributes_entities() {
+    let (sys_a, cluster_a) = node(&[]);
+    let (sys_b, cluster_b) = node(&[cluster_a.self_addr()]);
+    await_members(&cluster_a, 2);
+    await_members(&cluster_b, 2);
+
+    let (probe, listen) = probe();
+    let region_a = start_region(&sys_a, &cluster_a, probe.clone());
+    let region_b = start_region(&sys_b, &cluster_b, probe);
+
+    let node_a = cluster_a.self_addr().to_string();
+    let node_b = cluster_b.self_addr().to_string();
+
+    // the entities are distributed across both nodes
+    let placement = incr_all(&region_a, &listen);
+    assert_eq!(placement.len(), 20);
+    assert!(placement.values().any(|node| *node == node_a));
+    assert!(placement.values().any(|node| *node == node_b));
+
+    // every region routes an entity to the same node
+    assert_eq!(incr_all(&region_b, &listen), placement);
+
+    // shards are moved to the remaining node when a node leaves
+    cluster_b.leave();
+    await_members(&cluster_a, 1);
+    thread::sleep(Duration::from_millis(200));
+
+    let placement = incr_all(&region_a, &listen);
+    assert_eq!(placement.len(), 20);
+    assert!(placement.values().all(|node| *node == node_a));
+}
+
+#[test]
+fn sharding_reroutes_to_owner() {
+    let (sys_a, cluster_a) = node(&[]);
+    let (sys_b, cluster_b) = node(&[cluster_a.self_addr()]);
+    await_members(&cluster_a, 2);
+    await_members(&cluster_b, 2);
+
+    let (probe, listen) = probe();
+    let region_a = start_region(&sys_a, &cluster_a, probe.clone());
+    let _region_b = start_region(&sys_b, &cluster_b, probe);
+
+    let node_b = cluster_b.self_addr().to_string();
+    let placement = incr_all(&region_a, &listen);
+    let (id, _) = placement.iter().find(|(_, node)| **node == node_b).unwrap();
+
+    // a message delivered to a region that doesn't own the entity
+    // is routed to the owner
+    let msg = ShardRegionMsg::Deliver {
+        msg: EntityMsg::new(id, Incr),
+        hops: 1,
+    };
+    region_a.tell(msg, None);
+    assert_eq!(listen.recv(), format!("{} {}", id, node_b));
+}
+
+#[test]
+fn sharding_hands_off_shards() {
+    let (sys_a, cluster_a) = node(&[]);
+    let (probe, listen) = probe();
+    let region_a = start_region(&sys_a, &cluster_a, probe.clone());
+
+    let node_a = cluster_a.self_addr().to_string();
+    let placement = incr_all(&region_a, &listen);
+    assert!(placement.values().all(|node| *node == node_a));
+
+    // a node joins while messages are sent. The shards it now owns are
+    // handed off to it, and no message is lost meanwhile.
+    let start = Instant::now();
+    let (sys_b, cluster_b) = node(&[cluster_a.self_addr()]);
+    let _region_b = start_region(&sys_b, &cluster_b, probe);
+
+    for _ in 0..10 {
+        assert_eq!(incr_all(&region_a, &listen).len(), 20);
+        thread::sleep(Duration::from_millis(50));
+    }
+    await_members(&cluster_a, 2);
+    await_members(&cluster_b, 2);
+    thread::sleep(Duration::from_millis(200));
+
+    let node_b = cluster_b.self_addr().to_string();
+    let placement = incr_all(&region_a, &listen);
+    assert_eq!(placement.len(), 20);
+    assert!(placement.values().any(|node| *node == node_b));
+
+    // the shards were handed off without waiting for the timeout
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
@@ -0,0 +1,130 @@
+#[macro_use]
+extern crate riker_testkit;
+
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors_rs::cluster::{Cluster, ClusterSingleton};
+use actors_rs::remote::Remoting;
+use actors_rs::system::{JsonCodec, SystemBuilder};
+use actors_rs::*;
+
+use riker_testkit::probe::channel::{probe, ChannelProbe};
+use riker_testkit::probe::{Probe, ProbeReceive};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ping;
+
+/// Reports where it runs to the node's recorder
+#[derive(Default)]
+struct Scheduler {
+    recorder: Option<(String, ActorSelection)>,
+}
+
+impl Scheduler {
+    fn report(&self, evt: &str) {
+        if let Some((ref host, ref recorder)) = self.recorder {
+            recorder.try_tell(format!("{} {}", evt, host), None);
+        }
+    }
+}
+
+impl Actor for Scheduler {
+    type Msg = Ping;
+
+    fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
+        let recorder = ctx.select("/user/recorder").unwrap();
+        self.recorder = Some((ctx.system.host().to_string(), recorder));
+        self.report("started");
+    }
+
+    fn post_stop(&mut self) {
+        self.report("stopped");
+    }
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, _msg: Self::Msg, _sender: Sender) {
+        self.report("ping");
+    }
+}
+
+struct Recorder {
+    probe: ChannelProbe<(), String>,
+}
+
+impl ActorFactoryArgs<ChannelProbe<(), String>> for Recorder {
+    fn create_args(probe: ChannelProbe<(), String>) -> Self {
+        Recorder { probe }
+    }
+}
+
+impl Actor for Recorder {
+    type Msg = String;
+
+    fn recv(&mut self, _ctx: &Context<Self::Msg>, msg: Self::Msg, _sender: Sender) {
+        self.probe.event(msg);
+    }
+}
+
+fn node(seeds: &[SocketAddr], probe: ChannelProbe<(), String>) -> (Cluster, ActorRef<Ping>) {
+    let mut cfg = load_config();
+    cfg.set("cluster.heartbeat_interval_millis", 100).unwrap();
+    cfg.set("cluster.gossip_interval_millis", 100).unwrap();
+
+    let sys = SystemBuilder::new()
+        .name("singleton")
+        .cfg(cfg)
+        .create()
+        .unwrap();
+    sys.serialization()
+        .register::<Ping, _>("test.Ping", JsonCodec)
+        .unwrap();
+    sys.actor_of_args::<Recorder, _>("recorder", probe).unwrap();
+
+    let remote = Remoting::start(&sys, "127.0.0.1:0").unwrap();
+    let cluster = Cluster::join(&remote, seeds).unwrap();
+    await_up(&cluster, seeds.len() + 1);
+
+    let scheduler = ClusterSingleton::new(&cluster)
+        .unwrap()
+        .start::<Scheduler>("scheduler")
+        .unwrap();
+    (cluster, scheduler)
+}
+
+fn await_up(cluster: &Cluster, members: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !cluster.is_up() || cluster.members().len() < members {
+        assert!(Instant::now() < deadline, "cluster did not converge");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn singleton_runs_on_oldest_member() {
+    let (probe, listen) = probe();
+
+    let (cluster_a, proxy_a) = node(&[], probe.clone());
+    let node_a = cluster_a.self_addr();
+    p_assert_eq!(listen, format!("started {}", node_a));
+
+    let (cluster_b, proxy_b) = node(&[node_a], probe.clone());
+    let (_cluster_c, proxy_c) = node(&[node_a], probe);
+    await_up(&cluster_b, 3);
+
+    // every proxy forwards to the singleton on the oldest member
+    for proxy in &[&proxy_a, &proxy_b, &proxy_c] {
+        proxy.tell(Ping, None);
+        p_assert_eq!(listen, format!("ping {}", node_a));
+    }
+
+    // the singleton is handed over to the next oldest member
+    cluster_a.leave();
+    let node_b = cluster_b.self_addr();
+    p_assert_eq!(listen, format!("stopped {}", node_a));
+    p_assert_eq!(listen, format!("started {}", node_b));
+
+    proxy_c.tell(Ping, None);
+    p_assert_eq!(listen, format!("ping {}", node_b));
+}